@@ -0,0 +1,123 @@
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::models::config::Configuration;
+
+/// Create a podcast site from the audios published in Internet Archive.
+///
+/// Without a subcommand it syncs with Internet Archive, announces the new
+/// episodes and builds the site.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+pub struct Cli{
+    /// Configuration file
+    #[arg(short, long, global = true, default_value = "config.yml")]
+    pub config: String,
+    /// Directory with the episodes markdown files
+    #[arg(long, global = true)]
+    pub episodes_dir: Option<String>,
+    /// Directory with the pages markdown files
+    #[arg(long, global = true)]
+    pub pages_dir: Option<String>,
+    /// Directory with the templates
+    #[arg(long, global = true)]
+    pub templates_dir: Option<String>,
+    /// Output directory for the generated site
+    #[arg(long, global = true)]
+    pub public: Option<String>,
+    /// Never call Internet Archive and build only from the episodes front
+    /// matter, failing if any episode is incomplete
+    #[arg(long, global = true)]
+    pub offline: bool,
+    /// Report what sync, build and publish would do without writing any
+    /// file or sending any announcement
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    /// Format of the dry run report
+    #[arg(long, global = true, value_enum, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command{
+    /// Sync the episodes metadata with Internet Archive
+    Sync{
+        /// Request every item, ignoring the incremental sync cursor
+        #[arg(long)]
+        full: bool,
+    },
+    /// Generate the site from the local episodes and pages
+    Build,
+    /// Announce episodes with the publishers of the configuration
+    ///
+    /// Without identifiers it syncs with Internet Archive and announces the
+    /// new episodes. An episode is never announced twice with the same
+    /// publisher.
+    Publish{
+        /// Identifiers of the episodes to announce
+        identifiers: Vec<String>,
+        /// Retry the announcements that failed or are still pending
+        #[arg(long, conflicts_with = "identifiers")]
+        pending: bool,
+    },
+    /// Build the site in a temporary directory and serve it on localhost
+    ///
+    /// The site is built again every time the templates, episodes, pages,
+    /// assets or configuration change, and the browser reloads the page.
+    Serve{
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Check the configuration, episodes, pages and templates
+    Check,
+    /// Push the title, subject and description of local episodes to
+    /// Internet Archive
+    ///
+    /// The credentials are read from `IA_ACCESS_KEY` and `IA_SECRET_KEY`.
+    Push{
+        /// Identifiers of the episodes to push. All of them if empty
+        identifiers: Vec<String>,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Upload a new episode to Internet Archive
+    ///
+    /// The credentials are read from `IA_ACCESS_KEY` and `IA_SECRET_KEY`.
+    Upload{
+        /// Audio file of the episode
+        audio: String,
+        /// Markdown draft with the title, subject and description
+        draft: String,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ReportFormat{
+    Text,
+    Json,
+}
+
+impl Cli{
+    /// Overrides the configuration with the options given in the command
+    /// line.
+    pub fn apply(&self, configuration: &mut Configuration){
+        if let Some(episodes) = &self.episodes_dir{
+            configuration.set_episodes(episodes);
+        }
+        if let Some(pages) = &self.pages_dir{
+            configuration.set_pages(pages);
+        }
+        if let Some(templates) = &self.templates_dir{
+            configuration.set_templates(templates);
+        }
+        if let Some(public) = &self.public{
+            configuration.set_public(public);
+        }
+    }
+}
@@ -1,76 +1,524 @@
+mod cli;
 mod models;
+mod server;
+mod watch;
 
-use minijinja::context;
+use clap::Parser;
+use minijinja::{context, ErrorKind};
 use tracing_subscriber::{
     layer::SubscriberExt,
     util::SubscriberInitExt,
     EnvFilter
 };
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use std::str::FromStr;
+use chrono::{DateTime, Utc};
+use std::{collections::BTreeMap, path::Path};
 
+use cli::{Cli, Command, ReportFormat};
+use watch::{Change, SiteWatcher};
 use models::{
-    publisher::{
-        Telegram,
-        get_telegram_client,
-        Mastodon,
-        get_mastodon_client,
-    },
+    publisher::get_publisher,
+    archive::{SyncState, MetadataDiff, get_ias3_client},
+    draft::Draft,
     episode::Episode,
+    feed::Feed,
+    ledger::Ledger,
+    manifest::{Manifest, hash},
+    paginator::{paginate, group_by_year},
+    plan::Plan,
+    search::get_search_index,
+    sitemap::{Sitemap, get_robots},
+    tag::Tag,
+    validator::{check_feed, Severity},
     config::{
         Configuration,
         Post,
         Page,
+        Podcast,
+        FeedFormat,
     },
     ENV,
+    render,
+    set_templates,
 };
 
+const TEMPLATE_NAMES: [&str; 8] = [
+    "post.html",
+    "index.html",
+    "archive.html",
+    "page.html",
+    "tag.html",
+    "tags.html",
+    "search.html",
+    "statistics.html",
+];
+
+/// Optional templates with extra elements for the channel and each item
+/// of the feed.
+const FEED_CHANNEL_HOOK: &str = "feed_channel.xml";
+const FEED_ITEM_HOOK: &str = "feed_item.xml";
+
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
     let log_level = option_env!("RUST_LOG").unwrap_or("DEBUG");
-    let configuration = Configuration::read_configuration().await;
+    let mut configuration = Configuration::read_configuration(&cli.config).await;
+    cli.apply(&mut configuration);
 
     tracing_subscriber::registry()
         .with(EnvFilter::from_str(log_level).unwrap())
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init();
 
     debug!("Configuration: {:?}", configuration);
+    set_templates(configuration.get_templates());
+
+    if cli.offline && matches!(cli.command, Some(Command::Sync { .. }) | Some(Command::Publish { .. })) {
+        error!("Can not sync with Internet Archive in offline mode");
+        std::process::exit(1);
+    }
+    if cli.offline && matches!(cli.command, Some(Command::Upload { .. }) | Some(Command::Push { .. })) {
+        error!("Can not upload to Internet Archive in offline mode");
+        std::process::exit(1);
+    }
+    if cli.dry_run && matches!(cli.command, Some(Command::Serve { .. }) | Some(Command::Upload { .. })) {
+        error!("Can not serve or upload in dry run mode");
+        std::process::exit(1);
+    }
+    let mut plan = if cli.dry_run { Some(Plan::default()) } else { None };
+
+    match cli.command {
+        Some(Command::Sync { full }) => {
+            update(&configuration, full, &mut plan).await;
+        }
+        Some(Command::Build) => build(&configuration, cli.offline, &mut plan).await,
+        Some(Command::Publish { identifiers, pending }) => {
+            let episodes = if pending {
+                let ledger = read_ledger(&configuration).await;
+                let publishers: Vec<_> = configuration.get_publishers()
+                    .iter()
+                    .map(|config| config.get_name())
+                    .collect();
+                read_selected_episodes(&configuration, &ledger.get_pending(&publishers)).await
+            } else if identifiers.is_empty() {
+                update(&configuration, false, &mut plan).await
+            } else {
+                read_selected_episodes(&configuration, &identifiers).await
+            };
+            announce(&configuration, &episodes, &mut plan).await;
+        }
+        Some(Command::Serve { ref host, port }) => {
+            serve(&cli, configuration.clone(), host, port).await;
+        }
+        Some(Command::Check) => {
+            if !check(&configuration).await {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Push { identifiers, yes }) => {
+            push(&configuration, &identifiers, yes, &mut plan).await;
+        }
+        Some(Command::Upload { audio, draft }) => {
+            upload(&configuration, &audio, &draft).await;
+        }
+        None => {
+            if !cli.offline {
+                let episodes = update(&configuration, false, &mut plan).await;
+                announce(&configuration, &episodes, &mut plan).await;
+            }
+            build(&configuration, cli.offline, &mut plan).await;
+        }
+    }
 
-    update(&configuration).await;
+    if let Some(plan) = plan {
+        match cli.format {
+            ReportFormat::Text => print!("{}", plan),
+            ReportFormat::Json => println!("{}", plan.to_json()),
+        }
+    }
+}
 
-    let posts = read_episodes().await;
-    let pages = read_pages().await;
+/// Generates the site. In a dry run it only records the files that would be
+/// deleted.
+async fn build(configuration: &Configuration, offline: bool, plan: &mut Option<Plan>) {
+    let posts = read_episodes(configuration, offline, plan.is_some()).await;
+    let pages = read_pages(configuration).await;
     debug!("{:?}", posts);
     if posts.is_empty() {
         debug!("=== No audios found ===");
     } else {
         debug!("=== Generation ===");
-        create_public(&configuration).await;
-        generate_html(&configuration, &posts, &pages).await;
-        generate_index(&configuration, &posts, &pages).await;
-        generate_pages(&configuration, &pages).await;
-        generate_feed(&configuration, &posts).await;
-        generate_stats(&configuration, &posts, &pages).await;
-        let public = configuration.get_public().to_owned();
-        //TODO: Copy directory assets a /public/{podcast}/assets
-        //let output = format!("{}/style.css", public);
-        let assets_dir = format!("{}/assets", public);
-        create_dir(&assets_dir).await;
-        copy_all_files(configuration.get_assets(), &assets_dir).await;
-    }
-}
-
-async fn read_episodes() -> Vec<Post> {
+        if !generate_site(configuration, &posts, &pages, plan).await {
+            error!("The feed is not valid");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Generates the site writing only the files that changed since the last
+/// build, and deletes the ones that are not generated anymore. In a dry run
+/// it only records the files that would be deleted. Returns false if the
+/// feed is not valid.
+async fn generate_site(configuration: &Configuration, posts: &Vec<Post>, pages: &Vec<Post>,
+        plan: &mut Option<Plan>) -> bool {
+    let public = configuration.get_public();
+    let path = configuration.get_manifest();
+    // Without a manifest every file in public is from an unknown build, so
+    // the ones that are not generated now are deleted
+    let previous = match Manifest::read(path).await {
+        Some(previous) => previous,
+        None => {
+            let prefix = format!("{}/", public);
+            Manifest::from_files(list_files(public).await
+                .iter()
+                .filter_map(|file| file.strip_prefix(&prefix).map(str::to_string))
+                .collect())
+        }
+    };
+    let inputs = get_inputs(configuration).await;
+    if previous.get_inputs() == &inputs && previous.is_complete(public).await {
+        info!("Nothing changed since the last build");
+        return true;
+    }
+    let mut manifest = previous.next(inputs, plan.is_some());
+    if plan.is_none() {
+        create_public(configuration).await;
+    }
+    let valid = generate(configuration, posts, pages, &mut manifest).await;
+    for path in manifest.get_orphans() {
+        let output = format!("{}/{}", public, path);
+        match plan {
+            Some(plan) => plan.add_deletion(&output),
+            None => remove_output(public, &output).await,
+        }
+    }
+    if plan.is_none() {
+        if !valid {
+            manifest.clear_inputs();
+        }
+        if let Err(err) = manifest.save(path).await {
+            error!("Can not save the manifest {}. {:#}", path, err);
+        }
+    }
+    valid
+}
+
+/// Hashes of everything the site is generated from: the configuration, with
+/// the options of the command line, and the templates, episodes, pages and
+/// assets.
+async fn get_inputs(configuration: &Configuration) -> BTreeMap<String, String> {
+    let mut inputs = BTreeMap::new();
+    inputs.insert("version".to_string(), env!("CARGO_PKG_VERSION").to_string());
+    let config = serde_yaml::to_string(configuration).unwrap_or_default();
+    inputs.insert("config".to_string(), hash(config.as_bytes()));
+    for directory in [
+        configuration.get_templates(),
+        configuration.get_episodes(),
+        configuration.get_pages(),
+        configuration.get_assets(),
+    ] {
+        for path in list_files(directory).await {
+            if let Ok(content) = tokio::fs::read(&path).await {
+                inputs.insert(path, hash(&content));
+            }
+        }
+    }
+    inputs
+}
+
+/// Generates every page and feed of the site and copies the assets. Returns
+/// false if the feed is not valid.
+async fn generate(configuration: &Configuration, posts: &Vec<Post>, pages: &Vec<Post>,
+        manifest: &mut Manifest) -> bool {
+    generate_html(configuration, posts, pages, manifest).await;
+    generate_index(configuration, posts, pages, manifest).await;
+    generate_pages(configuration, pages, manifest).await;
+    generate_tags(configuration, posts, pages, manifest).await;
+    generate_feed(configuration, posts, manifest).await;
+    // The feed is not written in a dry run
+    let valid = manifest.is_dry_run() || validate_feed(configuration, posts).await;
+    generate_stats(configuration, posts, pages, manifest).await;
+    generate_search(configuration, posts, pages, manifest).await;
+    generate_sitemap(configuration, posts, pages, manifest).await;
+    copy_assets(configuration, manifest).await;
+    valid
+}
+
+async fn copy_assets(configuration: &Configuration, manifest: &mut Manifest) {
+    //TODO: Copy directory assets a /public/{podcast}/assets
+    //let output = format!("{}/style.css", public);
+    copy_all_files(manifest, configuration.get_assets(), configuration.get_public(),
+        "assets").await;
+}
+
+/// Builds the site in a temporary directory, serves it and builds it again
+/// every time the templates, episodes, pages, assets or configuration
+/// change. Episodes are read as they are, without syncing with Internet
+/// Archive.
+async fn serve(cli: &Cli, mut configuration: Configuration, host: &str, port: u16) {
+    let public = std::env::temp_dir().join(format!("iapodcast-{}", std::process::id()));
+    let public = public.to_string_lossy().to_string();
+    let url = format!("http://{}:{}", host, port);
+    let manifest = format!("{}.manifest.json", public);
+    configuration.set_public(&public);
+    configuration.set_manifest(&manifest);
+    configuration.set_url(&url);
+    rebuild(&configuration).await;
+    let mut watcher = watch(&configuration, &cli.config);
+    let (reload, _) = tokio::sync::broadcast::channel(16);
+    let sender = reload.clone();
+    let host = host.to_string();
+    tokio::spawn(async move {
+        server::serve(&public, &host, port, sender).await;
+    });
+    loop {
+        let change = tokio::select! {
+            change = watcher.next() => change,
+            _ = tokio::signal::ctrl_c() => None,
+        };
+        let Some(change) = change else {
+            break;
+        };
+        info!("Change detected. {:?}", change);
+        match change {
+            Change::Assets => rebuild(&configuration).await,
+            Change::Site => {
+                set_templates(configuration.get_templates());
+                rebuild(&configuration).await;
+            }
+            Change::Configuration => {
+                let mut reloaded = match Configuration::load(&cli.config).await {
+                    Ok(reloaded) => reloaded,
+                    Err(err) => {
+                        error!("Can not read {}. {:#}", &cli.config, err);
+                        continue;
+                    }
+                };
+                cli.apply(&mut reloaded);
+                reloaded.set_public(configuration.get_public());
+                reloaded.set_manifest(configuration.get_manifest());
+                reloaded.set_url(&url);
+                configuration = reloaded;
+                set_templates(configuration.get_templates());
+                watcher = watch(&configuration, &cli.config);
+                rebuild(&configuration).await;
+            }
+        }
+        reload.send(()).ok();
+    }
+    if let Err(err) = tokio::fs::remove_dir_all(configuration.get_public()).await {
+        error!("Can not delete {}. {:#}", configuration.get_public(), err);
+    }
+    tokio::fs::remove_file(configuration.get_manifest()).await.ok();
+}
+
+fn watch(configuration: &Configuration, config: &str) -> SiteWatcher {
+    match SiteWatcher::new(configuration, config) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            error!("Can not watch for changes. {:#}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Generates the site again from the local files, without saving the
+/// episodes, so that the watcher is not triggered again.
+async fn rebuild(configuration: &Configuration) {
+    let posts = read_episodes(configuration, false, true).await;
+    let pages = read_pages(configuration).await;
+    if !generate_site(configuration, &posts, &pages, &mut None).await {
+        error!("The feed is not valid");
+    }
+}
+
+/// Shows the differences of the local episodes with their items in Internet
+/// Archive and, once confirmed, pushes them. In a dry run the differences are
+/// recorded in `plan`.
+async fn push(configuration: &Configuration, identifiers: &[String], yes: bool,
+        plan: &mut Option<Plan>) {
+    let iaclient = configuration.get_iaclient();
+    let ias3 = get_ias3_client(iaclient.get_s3_url());
+    if ias3.is_none() && plan.is_none() {
+        error!("Set IA_ACCESS_KEY and IA_SECRET_KEY to push to Internet Archive");
+        std::process::exit(1);
+    }
+    let identifiers = if identifiers.is_empty() {
+        list_identifiers(configuration.get_episodes()).await
+    } else {
+        identifiers.to_vec()
+    };
+    for episode in read_selected_episodes(configuration, &identifiers).await {
+        let identifier = episode.get_identifier();
+        let remote = match iaclient.get_item_metadata(identifier) {
+            Ok(remote) => remote,
+            Err(err) => {
+                error!("Can not get metadata of {}. {:#}", identifier, err);
+                continue;
+            }
+        };
+        let diff = MetadataDiff::new(identifier, &remote, &episode.get_item_metadata(&remote));
+        if diff.is_empty() {
+            info!("{} is up to date", identifier);
+            continue;
+        }
+        if let Some(plan) = plan {
+            for change in diff.changes {
+                plan.add_push(identifier, change.field, change.remote, change.local);
+            }
+            continue;
+        }
+        // stdout is left for the report
+        eprint!("{}", diff);
+        if !(yes || confirm(&format!("Push changes of {}?", identifier))) {
+            continue;
+        }
+        if let Some(ias3) = &ias3 {
+            if let Err(err) = ias3.patch_metadata(iaclient.get_base_url(), &diff) {
+                error!("Can not push {}. {:#}", identifier, err);
+            }
+        }
+    }
+}
+
+fn confirm(question: &str) -> bool {
+    use std::io::Write;
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush().ok();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes" | "s" | "si" | "sí")
+}
+
+async fn list_identifiers(directory: &str) -> Vec<String> {
+    let mut identifiers = Vec::new();
+    if let Ok(mut episodes_dir) = tokio::fs::read_dir(directory).await {
+        while let Ok(Some(file)) = episodes_dir.next_entry().await {
+            let filename = file.file_name().to_string_lossy().to_string();
+            if let Some(identifier) = filename.strip_suffix(".md") {
+                identifiers.push(identifier.to_string());
+            }
+        }
+    }
+    identifiers.sort();
+    identifiers
+}
+
+/// Uploads `audio` to Internet Archive as a new item described by the
+/// markdown `draft`.
+async fn upload(configuration: &Configuration, audio: &str, draft: &str) {
+    let iaclient = configuration.get_iaclient();
+    let Some(ias3) = get_ias3_client(iaclient.get_s3_url()) else {
+        error!("Set IA_ACCESS_KEY and IA_SECRET_KEY to upload to Internet Archive");
+        std::process::exit(1);
+    };
+    let draft = match Draft::read(draft).await {
+        Ok(draft) => draft,
+        Err(err) => {
+            error!("Can not read draft {}. {:#}", draft, err);
+            std::process::exit(1);
+        }
+    };
+    match ias3.upload(iaclient, &draft, audio) {
+        Ok(identifier) => info!("Uploaded {}. Run `iapodcast sync` once it is published",
+            identifier),
+        Err(err) => {
+            error!("Can not upload {}. {:#}", audio, err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Reads the episodes, pages and templates without writing anything and
+/// returns `false` if any of them is wrong.
+async fn check(configuration: &Configuration) -> bool {
+    let mut ok = true;
+    let publishers = configuration.get_publishers()
+        .iter()
+        .map(|config| config.get_template());
+    for name in TEMPLATE_NAMES.map(String::from).into_iter().chain(publishers) {
+        if let Err(err) = ENV.read().unwrap().get_template(&name) {
+            error!("Template {} is wrong. {:#}", name, err);
+            ok = false;
+        }
+    }
+    for name in [FEED_CHANNEL_HOOK, FEED_ITEM_HOOK] {
+        match ENV.read().unwrap().get_template(name) {
+            Err(err) if err.kind() != ErrorKind::TemplateNotFound => {
+                error!("Template {} is wrong. {:#}", name, err);
+                ok = false;
+            }
+            _ => {}
+        }
+    }
+    match tokio::fs::read_dir(configuration.get_episodes()).await {
+        Ok(mut episodes_dir) => {
+            while let Ok(Some(file)) = episodes_dir.next_entry().await {
+                let filename = file.file_name().to_str().unwrap().to_string();
+                if filename.ends_with(".md") {
+                    if let Err(err) = Episode::read(configuration.get_episodes(), &filename).await {
+                        error!("Episode {} is wrong. {:#}", filename, err);
+                        ok = false;
+                    }
+                }
+            }
+        }
+        Err(err) => {
+            error!("Can not read {}. {:#}", configuration.get_episodes(), err);
+            ok = false;
+        }
+    }
+    if let Ok(mut pages_dir) = tokio::fs::read_dir(configuration.get_pages()).await {
+        while let Ok(Some(file)) = pages_dir.next_entry().await {
+            let filename = file.file_name().to_str().unwrap().to_string();
+            if filename.ends_with(".md") {
+                if let Err(err) = Page::new(configuration.get_pages(), &filename).await {
+                    error!("Page {} is wrong. {:#}", filename, err);
+                    ok = false;
+                }
+            }
+        }
+    }
+    if ok {
+        info!("Everything is ok");
+    }
+    ok
+}
+
+/// Reads the episodes. In offline mode an episode that lacks any of the
+/// fields filled in by Internet Archive stops the build, otherwise it is
+/// skipped.
+async fn read_episodes(configuration: &Configuration, offline: bool,
+        dry_run: bool) -> Vec<Post> {
     let mut posts = Vec::new();
-    let mut episodes_dir = tokio::fs::read_dir("episodes").await.unwrap();
+    let mut incomplete = 0;
+    let directory = configuration.get_episodes();
+    let mut episodes_dir = tokio::fs::read_dir(directory).await.unwrap();
     while let Some(file) = episodes_dir.next_entry().await.unwrap() {
         if file.metadata().await.unwrap().is_file() {
             let filename = file.file_name().to_str().unwrap().to_string();
             if filename.ends_with(".md") {
                 debug!("Read episode: {}", filename);
-                match Episode::new(&filename).await {
-                    Ok(episode) => posts.push(episode.get_post()),
+                let episode = if dry_run {
+                    Episode::read(directory, &filename).await
+                } else {
+                    Episode::new(directory, &filename).await
+                };
+                match episode {
+                    Ok(episode) => {
+                        let missing = episode.get_missing_fields();
+                        if missing.is_empty() {
+                            posts.push(episode.get_post());
+                        } else if offline {
+                            error!("Episode {} lacks {}. Run `iapodcast sync` to fill them in",
+                                episode.get_identifier(), missing.join(", "));
+                            incomplete += 1;
+                        } else {
+                            error!("Skip episode {}. It lacks {}",
+                                episode.get_identifier(), missing.join(", "));
+                        }
+                    }
                     Err(err) => {
                         error!("Can not write {}. {:#}", filename, err);
                         // render causes as well
@@ -84,19 +532,37 @@ async fn read_episodes() -> Vec<Post> {
             }
         }
     }
-    posts.sort_by(|a, b| b.date.cmp(&a.date));
+    if incomplete > 0 {
+        error!("Can not build offline. {} episodes are incomplete", incomplete);
+        std::process::exit(1);
+    }
+    posts.sort_by_key(|b| std::cmp::Reverse(b.date));
     posts
 }
 
-async fn read_pages() -> Vec<Post> {
+async fn read_selected_episodes(configuration: &Configuration,
+        identifiers: &[String]) -> Vec<Episode> {
+    let mut episodes = Vec::new();
+    for identifier in identifiers {
+        let filename = format!("{}.md", identifier);
+        match Episode::read(configuration.get_episodes(), &filename).await {
+            Ok(episode) => episodes.push(episode),
+            Err(err) => error!("Can not read episode {}. {:#}", identifier, err),
+        }
+    }
+    episodes
+}
+
+async fn read_pages(configuration: &Configuration) -> Vec<Post> {
     let mut posts = Vec::new();
-    if let Ok(mut pages_dir) = tokio::fs::read_dir("pages").await {
+    let directory = configuration.get_pages();
+    if let Ok(mut pages_dir) = tokio::fs::read_dir(directory).await {
         while let Some(file) = pages_dir.next_entry().await.unwrap() {
             if file.metadata().await.unwrap().is_file() {
                 let filename = file.file_name().to_str().unwrap().to_string();
                 if filename.ends_with(".md") {
                     debug!("Read pages: {}", filename);
-                    match Page::new(&filename).await {
+                    match Page::new(directory, &filename).await {
                         Ok(episode) => posts.push(episode.get_post()),
                         Err(err) => {
                             error!("Can not write {}. {:#}", filename, err);
@@ -111,26 +577,75 @@ async fn read_pages() -> Vec<Post> {
                 }
             }
         }
-        posts.sort_by(|a, b| b.date.cmp(&a.date));
+        posts.sort_by_key(|b| std::cmp::Reverse(b.date));
     }
     posts
 }
 
-async fn post_with_mastodon(configuration: &Configuration, episode: &Episode,
-        mastodon: &Mastodon) {
+async fn generate_feed(configuration: &Configuration, posts: &[Post], manifest: &mut Manifest) {
+    debug!("generate_feed");
+    let public = configuration.get_public().to_owned();
+    let feed = build_feed(configuration, configuration.get_podcast(), posts);
+    for feed_config in configuration.get_feeds() {
+        let feed_url = configuration.get_podcast().get_url(&feed_config.path);
+        let content = match feed_config.format {
+            FeedFormat::Rss => feed.to_rss(&feed_url),
+            FeedFormat::Json => feed.to_json(&feed_url),
+            FeedFormat::Atom => feed.to_atom(&feed_url),
+        };
+        match content {
+            Ok(content) => {
+                let (endpoint, filename) = feed_config.path
+                    .trim_start_matches('/')
+                    .rsplit_once('/')
+                    .unwrap_or(("", &feed_config.path));
+                write_post(manifest, &public, endpoint, Some(filename), &content).await;
+                debug!("write feed {}", feed_config.path);
+            }
+            Err(err) => error!("Could not generate feed {}: {:#}", feed_config.path, err),
+        }
+    }
+}
+
+/// Builds the feed of `podcast` with the extra elements of the template
+/// hooks.
+fn build_feed(configuration: &Configuration, podcast: &Podcast, posts: &[Post]) -> Feed {
+    let mut feed = Feed::new(
+        podcast,
+        configuration.get_iaclient(),
+        configuration.get_params(),
+        posts,
+    );
+    let ctx = context! {
+        podcast => podcast,
+        params => configuration.get_params(),
+        archive => configuration.get_iaclient().get_host(),
+    };
+    feed.extra = render_hook(FEED_CHANNEL_HOOK, ctx.clone());
+    for item in feed.items.iter_mut() {
+        item.extra = render_hook(FEED_ITEM_HOOK, context! { post => &item.post, ..ctx.clone() });
+    }
+    feed
+}
+
+/// Writes the search index, `search.json`, and the page that queries it.
+async fn generate_search(configuration: &Configuration, posts: &[Post], pages: &[Post],
+        manifest: &mut Manifest) {
+    debug!("generate_search");
+    let public = configuration.get_public().to_owned();
+    let index = get_search_index(posts, pages, &configuration.get_podcast().url);
+    write_post(manifest, &public, "", Some("search.json"), &index).await;
     let ctx = context! {
         podcast => configuration.get_podcast(),
         params => configuration.get_params(),
-        post => episode.get_post(),
+        pages => pages,
     };
-    let template = ENV.get_template("mastodon.html").unwrap();
-    match template.render(ctx) {
+    match render("search.html", ctx) {
         Ok(content) => {
-            debug!("{}", content);
-            mastodon.post(&content);
+            write_post(manifest, &public, "search", None, &content).await;
         }
         Err(err) => {
-            error!("Algo no ha funcionado correctamente. {:#}", err);
+            error!("Could not render template: {:#}", err);
             // render causes as well
             let mut err = &err as &dyn std::error::Error;
             while let Some(next_err) = err.source() {
@@ -141,27 +656,93 @@ async fn post_with_mastodon(configuration: &Configuration, episode: &Episode,
     }
 }
 
-async fn post_with_telegram(configuration: &Configuration, episode: &Episode,
-        telegram: &Telegram) {
-    let post = episode.get_post();
-    let audio = format!(
-        "https://archive.org/download/{}/{}",
-        &post.identifier, &post.filename
-    );
-    let ctx = context! {
+/// Writes `sitemap.xml`, with the index, the episodes, the pages and the
+/// statistics, and `robots.txt` pointing at it.
+async fn generate_sitemap(configuration: &Configuration, posts: &[Post], pages: &[Post],
+        manifest: &mut Manifest) {
+    debug!("generate_sitemap");
+    let public = configuration.get_public().to_owned();
+    let podcast = configuration.get_podcast();
+    let mut sitemap = Sitemap::default();
+    sitemap.add(&podcast.get_url(""), posts.iter().map(|post| post.date).max());
+    for post in posts {
+        sitemap.add(&podcast.get_url(&format!("{}/", post.slug)), Some(post.date));
+    }
+    for page in pages {
+        let lastmod = get_mtime(&page.filename).await.unwrap_or(page.date);
+        sitemap.add(&podcast.get_url(&format!("{}/", page.slug)), Some(lastmod));
+    }
+    sitemap.add(&podcast.get_url("statistics/"),
+        get_mtime(&format!("{}/statistics/index.html", public)).await);
+    match sitemap.to_xml() {
+        Ok(content) => write_post(manifest, &public, "", Some("sitemap.xml"), &content).await,
+        Err(err) => error!("Could not generate sitemap: {:#}", err),
+    }
+    let robots = get_robots(configuration.get_robots(), &podcast.get_url("sitemap.xml"));
+    write_post(manifest, &public, "", Some("robots.txt"), &robots).await;
+}
+
+/// Last modification time of `path`, if it exists.
+async fn get_mtime(path: &str) -> Option<DateTime<Utc>> {
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    metadata.modified().ok().map(DateTime::<Utc>::from)
+}
+
+/// Writes a page for each subject of the episodes, with its own feed if
+/// configured, and the index of every subject.
+async fn generate_tags(configuration: &Configuration, posts: &[Post], pages: &[Post],
+        manifest: &mut Manifest) {
+    debug!("generate_tags");
+    let public = configuration.get_public().to_owned();
+    let tags_config = configuration.get_tags();
+    let path = tags_config.path.trim_matches('/');
+    let tags = Tag::from_posts(posts);
+    for tag in &tags {
+        let endpoint = format!("{}/{}", path, tag.slug);
+        let ctx = context!(
+            podcast => configuration.get_podcast(),
+            params => configuration.get_params(),
+            tag => tag,
+            tags_path => path,
+            feed => tags_config.feed,
+            pages => pages,
+        );
+        match render("tag.html", ctx) {
+            Ok(content) => write_post(manifest, &public, &endpoint, None, &content).await,
+            Err(err) => {
+                error!("Could not render template: {:#}", err);
+                // render causes as well
+                let mut err = &err as &dyn std::error::Error;
+                while let Some(next_err) = err.source() {
+                    error!("caused by: {:#}", next_err);
+                    err = next_err;
+                }
+            }
+        }
+        if tags_config.feed {
+            let mut podcast = configuration.get_podcast().clone();
+            podcast.title = format!("{} - {}", podcast.title, tag.name);
+            podcast.feed_url = format!("{}/feed.xml", endpoint);
+            podcast.guid = None;
+            podcast.fill_guid();
+            let feed = build_feed(configuration, &podcast, &tag.posts);
+            match feed.to_rss(&podcast.get_feed_url()) {
+                Ok(content) => write_post(manifest, &public, &endpoint, Some("feed.xml"), &content).await,
+                Err(err) => error!("Could not generate feed for {}: {:#}", tag.slug, err),
+            }
+        }
+    }
+    let ctx = context!(
         podcast => configuration.get_podcast(),
         params => configuration.get_params(),
-        audio => audio,
-        post => episode.get_post(),
-    };
-    let template = ENV.get_template("telegram.html").unwrap();
-    match template.render(ctx) {
-        Ok(caption) => {
-            info!("Caption: {caption}");
-            telegram.send_audio(&audio, &caption);
-        }
+        tags => tags,
+        tags_path => path,
+        pages => pages,
+    );
+    match render("tags.html", ctx) {
+        Ok(content) => write_post(manifest, &public, path, None, &content).await,
         Err(err) => {
-            error!("Algo no ha funcionado correctamente. {:#}", err);
+            error!("Could not render template: {:#}", err);
             // render causes as well
             let mut err = &err as &dyn std::error::Error;
             while let Some(next_err) = err.source() {
@@ -172,39 +753,90 @@ async fn post_with_telegram(configuration: &Configuration, episode: &Episode,
     }
 }
 
-async fn generate_feed(configuration: &Configuration, posts: &[Post]) {
-    debug!("generate_feed");
-    let public = configuration.get_public().to_owned();
-    let ctx = context! {
-        podcast => configuration.get_podcast(),
-        params => configuration.get_params(),
-        posts => posts,
+/// Renders the optional template `name`. Returns None if it does not exist
+/// or can not be rendered.
+fn render_hook(name: &str, ctx: minijinja::Value) -> Option<String> {
+    let env = ENV.read().unwrap();
+    let template = match env.get_template(name) {
+        Ok(template) => template,
+        Err(err) if err.kind() == ErrorKind::TemplateNotFound => return None,
+        Err(err) => {
+            error!("Template {} is wrong. {:#}", name, err);
+            return None;
+        }
     };
-    let template = ENV.get_template("feed.xml").unwrap();
     match template.render(ctx) {
-        Ok(content) => {
-            write_post(
-                &public,
-                "",
-                Some(&configuration.get_podcast().feed_url),
-                &content,
-            )
-            .await;
-            debug!("write feed");
-        }
+        Ok(content) => Some(content),
         Err(err) => {
-            error!("Could not render template: {:#}", err);
+            error!("Could not render template {}: {:#}", name, err);
             // render causes as well
             let mut err = &err as &dyn std::error::Error;
             while let Some(next_err) = err.source() {
                 error!("caused by: {:#}", next_err);
                 err = next_err;
             }
+            None
+        }
+    }
+}
+
+/// Checks the generated RSS feeds, the ones of the tags included, and logs
+/// every violation. Returns false if there is any error.
+async fn validate_feed(configuration: &Configuration, posts: &[Post]) -> bool {
+    debug!("validate_feed");
+    let mut ok = true;
+    for feed_config in configuration.get_feeds() {
+        if feed_config.format != FeedFormat::Rss {
+            continue;
+        }
+        let filename = format!("{}/{}", configuration.get_public(),
+            feed_config.path.trim_start_matches('/'));
+        ok &= validate_feed_file(&filename, posts).await;
+    }
+    let tags_config = configuration.get_tags();
+    if tags_config.feed {
+        for tag in Tag::from_posts(posts) {
+            let filename = format!("{}/{}/{}/feed.xml", configuration.get_public(),
+                tags_config.path.trim_matches('/'), tag.slug);
+            ok &= validate_feed_file(&filename, &tag.posts).await;
+        }
+    }
+    ok
+}
+
+/// Checks the RSS feed `filename` of `posts` and logs every violation.
+/// Returns false if there is any error.
+async fn validate_feed_file(filename: &str, posts: &[Post]) -> bool {
+    let xml = match tokio::fs::read_to_string(filename).await {
+        Ok(xml) => xml,
+        Err(err) => {
+            error!("Can not read {}. {:#}", filename, err);
+            return false;
+        }
+    };
+    let mut ok = true;
+    match check_feed(&xml, posts) {
+        Ok(violations) => {
+            for violation in violations {
+                match violation.severity {
+                    Severity::Error => {
+                        error!("{}: {}", filename, violation);
+                        ok = false;
+                    }
+                    Severity::Warning => warn!("{}: {}", filename, violation),
+                }
+            }
+        }
+        Err(err) => {
+            error!("Can not parse {}. {:#}", filename, err);
+            ok = false;
         }
     }
+    ok
 }
 
-async fn generate_stats(configuration: &Configuration, posts: &Vec<Post>, pages: &Vec<Post>) {
+async fn generate_stats(configuration: &Configuration, posts: &Vec<Post>, pages: &Vec<Post>,
+        manifest: &mut Manifest) {
     debug!("generate_stats");
     let public = configuration.get_public().to_owned();
     let ctx = context! {
@@ -213,12 +845,10 @@ async fn generate_stats(configuration: &Configuration, posts: &Vec<Post>, pages:
         posts => posts,
         pages => pages,
     };
-    let template = ENV.get_template("statistics.html").unwrap();
-    match template.render(ctx) {
+    match render("statistics.html", ctx) {
         Ok(content) => {
             debug!("{}", content);
-            create_dir(&format!("{}/{}", public, "statistics")).await;
-            write_post(&public, "statistics", None, &content).await;
+            write_post(manifest, &public, "statistics", None, &content).await;
         }
         Err(err) => {
             error!("Could not render template: {:#}", err);
@@ -232,35 +862,72 @@ async fn generate_stats(configuration: &Configuration, posts: &Vec<Post>, pages:
     }
 }
 
-async fn generate_index(configuration: &Configuration, posts: &Vec<Post>,
-    pages: &Vec<Post>) {
+/// Writes the index split in pages and the archive of each year.
+async fn generate_index(configuration: &Configuration, posts: &[Post],
+    pages: &[Post], manifest: &mut Manifest) {
     debug!("generate_index");
     let public = configuration.get_public().to_owned();
-    let ctx = context! {
-        podcast => configuration.get_podcast(),
-        params => configuration.get_params(),
-        posts => posts,
-        pages => pages,
-    };
-    let template = ENV.get_template("index.html").unwrap();
-    match template.render(ctx) {
-        Ok(content) => {
-            debug!("{}", content);
-            write_post(&public, "", None, &content).await;
+    let index = configuration.get_index();
+    let archive_path = index.archive.trim_matches('/');
+    let years = group_by_year(posts);
+    for pager in paginate(posts, index.per_page, index.path.trim_matches('/')) {
+        let ctx = context! {
+            podcast => configuration.get_podcast(),
+            params => configuration.get_params(),
+            posts => pager.posts,
+            pages => pages,
+            current_page => pager.number,
+            total_pages => pager.total,
+            prev => pager.prev,
+            next => pager.next,
+            years => years,
+            archive_path => archive_path,
+        };
+        match render("index.html", ctx) {
+            Ok(content) => {
+                debug!("{}", content);
+                write_post(manifest, &public, &pager.path, None, &content).await;
+            }
+            Err(err) => {
+                error!("Could not render template: {:#}", err);
+                // render causes as well
+                let mut err = &err as &dyn std::error::Error;
+                while let Some(next_err) = err.source() {
+                    error!("caused by: {:#}", next_err);
+                    err = next_err;
+                }
+            }
         }
-        Err(err) => {
-            error!("Could not render template: {:#}", err);
-            // render causes as well
-            let mut err = &err as &dyn std::error::Error;
-            while let Some(next_err) = err.source() {
-                error!("caused by: {:#}", next_err);
-                err = next_err;
+    }
+    for year in &years {
+        let ctx = context! {
+            podcast => configuration.get_podcast(),
+            params => configuration.get_params(),
+            year => year,
+            years => years,
+            archive_path => archive_path,
+            pages => pages,
+        };
+        match render("archive.html", ctx) {
+            Ok(content) => {
+                let endpoint = format!("{}/{}", archive_path, year.year);
+                write_post(manifest, &public, &endpoint, None, &content).await;
+            }
+            Err(err) => {
+                error!("Could not render template: {:#}", err);
+                // render causes as well
+                let mut err = &err as &dyn std::error::Error;
+                while let Some(next_err) = err.source() {
+                    error!("caused by: {:#}", next_err);
+                    err = next_err;
+                }
             }
         }
     }
 }
 
-async fn generate_pages(configuration: &Configuration, pages: &Vec<Post>) {
+async fn generate_pages(configuration: &Configuration, pages: &Vec<Post>,
+        manifest: &mut Manifest) {
     debug!("generate_pages");
     let public = configuration.get_public().to_owned();
     for page in pages {
@@ -270,13 +937,11 @@ async fn generate_pages(configuration: &Configuration, pages: &Vec<Post>) {
             params => configuration.get_params(),
             page => page,
         );
-        let template = ENV.get_template("page.html").unwrap();
-        match template.render(ctx) {
+        match render("page.html", ctx) {
             Ok(content) => {
                 debug!("{}", &content);
                 debug!("Page: {:?}", &page);
-                create_dir(&format!("{}/{}", public, &page.slug)).await;
-                write_post(&public, &page.slug, None, &content).await
+                write_post(manifest, &public, &page.slug, None, &content).await
             }
             Err(err) => {
                 error!("Could not render template: {:#}", err);
@@ -294,7 +959,7 @@ async fn generate_pages(configuration: &Configuration, pages: &Vec<Post>) {
 }
 
 async fn generate_html(configuration: &Configuration, posts: &[Post],
-        pages: &[Post]) {
+        pages: &[Post], manifest: &mut Manifest) {
     debug!("generate_html");
     let public = configuration.get_public().to_owned();
     for post in posts {
@@ -302,16 +967,16 @@ async fn generate_html(configuration: &Configuration, posts: &[Post],
         let ctx = context!(
             podcast => configuration.get_podcast(),
             params => configuration.get_params(),
+            archive => configuration.get_iaclient().get_host(),
             post => post,
             pages => pages,
+            tags_path => configuration.get_tags().path.trim_matches('/'),
         );
-        let template = ENV.get_template("post.html").unwrap();
-        match template.render(ctx) {
+        match render("post.html", ctx) {
             Ok(content) => {
                 debug!("{}", &content);
                 debug!("Post: {:?}", &post);
-                create_dir(&format!("{}/{}", public, &post.slug)).await;
-                write_post(&public, &post.slug, None, &content).await
+                write_post(manifest, &public, &post.slug, None, &content).await
             }
             Err(err) => {
                 error!("Could not render template: {:#}", err);
@@ -326,22 +991,91 @@ async fn generate_html(configuration: &Configuration, posts: &[Post],
     }
 }
 
-async fn update(configuration: &Configuration) {
+/// Syncs the episodes with Internet Archive and returns the new ones. With a
+/// `sync` section in the configuration only the newer items are requested,
+/// unless `full` is set or the full refresh is due. In a dry run nothing is
+/// saved and the changes are recorded in `plan`.
+async fn update(configuration: &Configuration, full: bool,
+        plan: &mut Option<Plan>) -> Vec<Episode> {
     debug!("update");
-    let mastodon_client = get_mastodon_client();
-    let telegram_client = get_telegram_client();
+    let directory = configuration.get_episodes();
     let mut new_docs = Vec::new();
+    let mut new_episodes = Vec::new();
     let iaclient = configuration.get_iaclient();
-    let docs = iaclient.get_all_docs();
-    for doc in docs {
-        if doc.exists().await {
+    let now = Utc::now();
+    let mut ledger = read_ledger(configuration).await;
+    let mut state = match configuration.get_sync() {
+        Some(sync) => Some((sync, SyncState::read(&sync.state).await)),
+        None => None,
+    };
+    let (docs, full) = match &state {
+        Some((sync, state)) if !full && !state.needs_full_sync(sync.full_refresh_days, now) => {
+            let since = state.last_publicdate.unwrap();
+            info!("Incremental sync since {}", since);
+            (iaclient.get_new_docs(&since, state.last_number), false)
+        }
+        _ => {
+            info!("Full sync");
+            (iaclient.get_all_docs(), true)
+        }
+    };
+    let docs = match docs {
+        Ok(docs) => docs,
+        Err(err) => {
+            error!("Can not get the docs of Internet Archive. {:#}", err);
+            // render causes as well
+            let mut err = &err as &dyn std::error::Error;
+            while let Some(next_err) = err.source() {
+                error!("caused by: {:#}", next_err);
+                err = next_err;
+            }
+            return new_episodes;
+        }
+    };
+    // Docs that are in the episodes directory after the sync, and the ones
+    // that could not be saved, so that the cursor does not move past them
+    let mut seen = Vec::new();
+    let mut failed = Vec::new();
+    for mut doc in docs {
+        if doc.exists(directory).await {
+            seen.push((doc.get_datetime(), doc.get_number()));
             debug!("Doc {} exists", doc.get_identifier());
             debug!("Doc: {:?}", &doc);
             let filename = doc.get_post_filename();
             //BUG: Esto hay que revisar
-            match Episode::new(&filename).await {
-                Ok(ref mut episode) => {
-                    if episode.get_downloads() != doc.get_downloads()
+            let episode = if plan.is_some() {
+                Episode::read(directory, &filename).await
+            } else {
+                Episode::new(directory, &filename).await
+            };
+            match episode {
+                Ok(mut episode) => {
+                    let incomplete = !episode.get_missing_fields().is_empty()
+                        || !episode.has_audios();
+                    if incomplete {
+                        match doc.complete(iaclient.get_base_url()) {
+                            Ok(_) => episode.complete_from(&doc),
+                            Err(e) => error!("Can' complete doc: {e}"),
+                        }
+                    }
+                    let merge = episode.merge(&doc, configuration.get_merge());
+                    for field in &merge.conflicts {
+                        warn!("Conflict in {} of episode {}. Keeping the local value",
+                            field, episode.get_identifier());
+                    }
+                    if let Some(plan) = plan {
+                        if episode.get_downloads() != doc.get_downloads() {
+                            plan.add_downloads(episode.get_identifier(),
+                                episode.get_downloads(), doc.get_downloads());
+                        }
+                        for field in &merge.updated {
+                            plan.add_merge(episode.get_identifier(), field);
+                        }
+                        for field in &merge.conflicts {
+                            plan.add_conflict(episode.get_identifier(), field);
+                        }
+                    } else if incomplete || !merge.updated.is_empty()
+                        || episode.get_downloads() != doc.get_downloads()
                     {
                         episode.set_downloads(doc.get_downloads());
                         match episode.save().await {
@@ -373,27 +1107,31 @@ async fn update(configuration: &Configuration) {
         }
     }
     for mut doc in new_docs {
-        if let Err(e) = doc.complete() {
+        let datetime = doc.get_datetime();
+        let number = doc.get_number();
+        if let Err(e) = doc.complete(iaclient.get_base_url()) {
             error!("Can' complete doc: {e}");
+            failed.push(datetime);
         }else{
-            let episode: Episode = doc.into();
+            let episode = Episode::from_doc(doc, directory);
+            if let Some(plan) = plan {
+                plan.add_new_episode(episode.get_identifier(), &episode.get_post().title);
+                new_episodes.push(episode);
+                continue;
+            }
             match episode.save().await {
                 Ok(_) => {
-                    match &telegram_client {
-                        Some(client) => {
-                            post_with_telegram(configuration, &episode, client).await;
-                        }
-                        None => {}
-                    };
-                    match &mastodon_client {
-                        Some(client) => {
-                            post_with_mastodon(configuration, &episode, client).await;
-                        }
-                        None => {}
-                    }
                     info!("Episode {} saved", episode.get_identifier());
+                    seen.push((datetime, number));
+                    // Recorded so that publish --pending announces it even
+                    // if this run does not
+                    for config in configuration.get_publishers() {
+                        ledger.add_pending(episode.get_identifier(), config.get_name(), now);
+                    }
+                    new_episodes.push(episode);
                 }
                 Err(err) => {
+                    failed.push(datetime);
                     error!("2 Can not save episode {}. {:#}", episode.get_identifier(), err);
                     // render causes as well
                     let mut err = &err as &dyn std::error::Error;
@@ -405,6 +1143,116 @@ async fn update(configuration: &Configuration) {
             }
         }
     }
+    if plan.is_none() && !new_episodes.is_empty() {
+        if let Err(err) = ledger.save(configuration.get_ledger()).await {
+            error!("Can not save ledger {}. {:#}", configuration.get_ledger(), err);
+        }
+    }
+    if let Some((sync, state)) = &mut state {
+        if plan.is_none() {
+            state.advance(&seen, failed.iter().min(), full, now);
+            if let Err(err) = state.save(&sync.state).await {
+                error!("Can not save sync state {}. {:#}", &sync.state, err);
+            }
+        }
+    }
+    new_episodes
+}
+
+/// Announces the episodes with every publisher enabled in the configuration,
+/// recording the result in the ledger. The announcements already sent are
+/// skipped, and the ones of publishers without credentials are left pending.
+/// In a dry run the rendered announcements are recorded in `plan` and the
+/// ledger is not changed.
+async fn announce(configuration: &Configuration, episodes: &[Episode],
+        plan: &mut Option<Plan>) {
+    debug!("announce");
+    let path = configuration.get_ledger();
+    let mut ledger = read_ledger(configuration).await;
+    let publishers: Vec<_> = configuration.get_publishers()
+        .iter()
+        .map(|config| (config.get_name(), config.get_template(),
+            get_publisher(config, configuration)))
+        .collect();
+    for episode in episodes {
+        let identifier = episode.get_identifier();
+        let post = episode.get_post();
+        let audio = configuration.get_iaclient()
+            .get_download_url(&post.identifier, &post.filename);
+        let ctx = context! {
+            podcast => configuration.get_podcast(),
+            params => configuration.get_params(),
+            audio => audio,
+            post => post,
+        };
+        for (name, template, publisher) in &publishers {
+            if ledger.is_sent(identifier, name) {
+                info!("{} was already announced in {}", identifier, name);
+                continue;
+            }
+            let Some(publisher) = publisher else {
+                match plan {
+                    Some(plan) => plan.add_skipped(name, identifier),
+                    None => ledger.add_pending(identifier, name, Utc::now()),
+                }
+                continue;
+            };
+            let result = match render(template, ctx.clone()) {
+                Ok(content) => {
+                    debug!("{}", content);
+                    if let Some(plan) = plan {
+                        plan.add_announcement(name, identifier, &content);
+                        continue;
+                    }
+                    publisher.announce(&post, &content)
+                }
+                Err(err) => {
+                    error!("Algo no ha funcionado correctamente. {:#}", err);
+                    // render causes as well
+                    let mut source = &err as &dyn std::error::Error;
+                    while let Some(next_err) = source.source() {
+                        error!("caused by: {:#}", next_err);
+                        source = next_err;
+                    }
+                    if plan.is_some() {
+                        continue;
+                    }
+                    Err(err.into())
+                }
+            };
+            match result {
+                Ok(receipt) => {
+                    info!("Announced {} in {}", identifier, name);
+                    ledger.add_sent(identifier, name, receipt, Utc::now());
+                }
+                Err(err) => {
+                    error!("Can not announce {} in {}. {:#}", identifier, name, err);
+                    ledger.add_failure(identifier, name, &err.to_string(), Utc::now());
+                }
+            }
+            // Saved after every announcement, so that none is lost
+            if let Err(err) = ledger.save(path).await {
+                error!("Can not save ledger {}. {:#}", path, err);
+            }
+        }
+    }
+    if plan.is_none() {
+        if let Err(err) = ledger.save(path).await {
+            error!("Can not save ledger {}. {:#}", path, err);
+        }
+    }
+}
+
+/// Reads the ledger of the announcements, stopping if it exists but can not
+/// be read, as an empty one would announce again every episode.
+async fn read_ledger(configuration: &Configuration) -> Ledger {
+    match Ledger::read(configuration.get_ledger()).await {
+        Ok(ledger) => ledger,
+        Err(err) => {
+            error!("{:#}", err);
+            std::process::exit(1);
+        }
+    }
 }
 
 fn clean_path(path: &str) -> &str {
@@ -422,18 +1270,37 @@ fn clean_path(path: &str) -> &str {
     }
 }
 
-async fn write_post(base: &str, endpoint: &str, filename: Option<&str>, content: &str) {
+async fn write_post(manifest: &mut Manifest, base: &str, endpoint: &str,
+        filename: Option<&str>, content: &str) {
     debug!(
         "write_post. Base: {base}. Endpoint {endpoint}. Filename: {:?}",
         filename
     );
     let endpoint = clean_path(endpoint);
     let filename = filename.unwrap_or("index.html");
-    let output = if endpoint.is_empty() {
-        format!("{}/{}", base, filename)
+    let path = if endpoint.is_empty() {
+        filename.to_string()
     } else {
-        format!("{}/{}/{}", base, endpoint, filename)
+        format!("{}/{}", endpoint, filename)
     };
+    write_file(manifest, base, &path, content.as_bytes()).await;
+}
+
+/// Writes `content` in `path`, relative to `base`, unless it is the same as
+/// in the last build. Nothing is written in a dry run.
+async fn write_file(manifest: &mut Manifest, base: &str, path: &str, content: &[u8]) {
+    let output = format!("{}/{}", base, path);
+    let changed = manifest.add_output(path, content);
+    if manifest.is_dry_run() {
+        return;
+    }
+    if !changed && tokio::fs::metadata(&output).await.is_ok() {
+        debug!("{} is up to date", &output);
+        return;
+    }
+    if let Some(parent) = Path::new(&output).parent() {
+        create_dir(&parent.to_string_lossy()).await;
+    }
     match tokio::fs::write(&output, content).await {
         Ok(_) => debug!("post {} created", &output),
         Err(err) => {
@@ -449,50 +1316,71 @@ async fn write_post(base: &str, endpoint: &str, filename: Option<&str>, content:
     }
 }
 
-async fn copy_all_files(from_dir: &str, to_dir: &str) {
-    debug!("Going to copy from {} to {}", from_dir, to_dir);
+/// Deletes `output`, generated by the last build but not by this one, and
+/// its directories up to `public` if they are left empty.
+async fn remove_output(public: &str, output: &str) {
+    match tokio::fs::remove_file(output).await {
+        Ok(_) => info!("Removed {}", output),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => error!("Can not delete {}. {:#}", output, err),
+    }
+    let mut dir = Path::new(output).parent();
+    while let Some(path) = dir {
+        if path == Path::new(public) || tokio::fs::remove_dir(path).await.is_err() {
+            break;
+        }
+        dir = path.parent();
+    }
+}
+
+/// Returns every file below `dir`, or nothing if it does not exist.
+async fn list_files(dir: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_string()];
+    while let Some(dir) = pending.pop() {
+        if let Ok(mut entries) = tokio::fs::read_dir(&dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path().to_string_lossy().to_string();
+                match entry.metadata().await {
+                    Ok(metadata) if metadata.is_dir() => pending.push(path),
+                    Ok(_) => files.push(path),
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+async fn copy_all_files(manifest: &mut Manifest, from_dir: &str, base: &str, to_dir: &str) {
+    debug!("Going to copy from {} to {}/{}", from_dir, base, to_dir);
     let mut episodes_dir = tokio::fs::read_dir(from_dir).await.unwrap();
     while let Some(file) = episodes_dir.next_entry().await.unwrap() {
         if file.metadata().await.unwrap().is_file() {
             let filename = file.file_name().to_str().unwrap().to_string();
             let input_file = format!("{}/{}", from_dir, filename);
-            let output_file = format!("{}/{}", to_dir, filename);
-            copy_file(&input_file, &output_file).await;
+            match tokio::fs::read(&input_file).await {
+                Ok(content) => {
+                    let output_file = format!("{}/{}", to_dir, filename);
+                    write_file(manifest, base, &output_file, &content).await;
+                }
+                Err(err) => {
+                    error!("Cant copy from {}. {:#}", input_file, err);
+                    let mut err = &err as &dyn std::error::Error;
+                    while let Some(next_err) = err.source() {
+                        error!("caused by: {:#}", next_err);
+                        err = next_err;
+                    }
+                    std::process::exit(1);
+                }
+            }
         }
     }
 }
 
 async fn create_dir(output: &str) {
     debug!("Going to create : {}", &output);
-    let exists = match tokio::fs::metadata(&output).await {
-        Ok(metadata) => {
-            debug!("Output dir {} exists", &output);
-            metadata.is_dir()
-        }
-        Err(err) => {
-            debug!("Can not get metadata for dir {}, {:#}", &output, err);
-            let mut err = &err as &dyn std::error::Error;
-            while let Some(next_err) = err.source() {
-                debug!("caused by: {:#}", next_err);
-                err = next_err;
-            }
-            false
-        }
-    };
-    if exists {
-        match tokio::fs::remove_dir_all(&output).await {
-            Ok(_) => debug!("Directory {} removed", output),
-            Err(err) => {
-                error!("Cant delete directory {}, {:#}", &output, err);
-                let mut err = &err as &dyn std::error::Error;
-                while let Some(next_err) = err.source() {
-                    error!("caused by: {:#}", next_err);
-                    err = next_err;
-                }
-                std::process::exit(1);
-            }
-        }
-    }
     match tokio::fs::create_dir_all(&output).await {
         Ok(_) => debug!("Directory {} created", output),
         Err(err) => {
@@ -507,64 +1395,11 @@ async fn create_dir(output: &str) {
     }
 }
 
-pub async fn copy_file(from: &str, to: &str) {
-    match tokio::fs::copy(from, to).await {
-        Ok(_) => debug!("Copied from {} to {}", from, to),
-        Err(err) => {
-            error!("Cant copy from {} to {}. {:#}", from, to, err);
-            let mut err = &err as &dyn std::error::Error;
-            while let Some(next_err) = err.source() {
-                error!("caused by: {:#}", next_err);
-                err = next_err;
-            }
-            std::process::exit(1);
-        }
-    }
-}
-
+/// Creates the public directory if it does not exist. The files of the last
+/// build are kept, so that only the ones that change are written again.
 pub async fn create_public(configuration: &Configuration) {
     debug!("create_public");
     let output = configuration.get_public();
     debug!("Output dir: {}", &output);
-    let exists = match tokio::fs::metadata(output).await {
-        Ok(metadata) => {
-            debug!("Output dir {} exists", &output);
-            metadata.is_dir()
-        }
-        Err(err) => {
-            debug!("Output dir {}, {:#}", &output, err);
-            let mut err = &err as &dyn std::error::Error;
-            while let Some(next_err) = err.source() {
-                error!("caused by: {:#}", next_err);
-                err = next_err;
-            }
-            false
-        }
-    };
-    if exists {
-        match tokio::fs::remove_dir_all(output).await {
-            Ok(_) => debug!("Directory {} removed", output),
-            Err(err) => {
-                error!("Cant delete directory {}, {}", output, err);
-                let mut err = &err as &dyn std::error::Error;
-                while let Some(next_err) = err.source() {
-                    error!("caused by: {:#}", next_err);
-                    err = next_err;
-                }
-                std::process::exit(1);
-            }
-        }
-    }
-    match tokio::fs::create_dir_all(output).await {
-        Ok(_) => debug!("Directory {} created", output),
-        Err(err) => {
-            error!("Cant create directory {}, {}", output, err);
-            let mut err = &err as &dyn std::error::Error;
-            while let Some(next_err) = err.source() {
-                error!("caused by: {:#}", next_err);
-                err = next_err;
-            }
-            std::process::exit(1);
-        }
-    }
+    create_dir(output).await;
 }
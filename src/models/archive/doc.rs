@@ -1,12 +1,11 @@
 use serde::{Serialize, Deserialize, Deserializer, de};
-use regex::Regex;
 use chrono::{DateTime, Utc};
 use tracing::{info, error, debug};
 use tokio::fs;
 use std::{fmt, marker::PhantomData};
 
 use super::{
-    BASE_URL,
+    metadata::{AudioFile, MetadataError, parse_audio_files},
     super::{
         error::Error,
         utils::{
@@ -38,6 +37,8 @@ pub struct Doc{
     size: u64,
     #[serde(default = "default_u64")]
     length: u64,
+    #[serde(default)]
+    audios: Vec<AudioFile>,
 }
 
 fn default_empty() -> String {
@@ -81,54 +82,30 @@ fn string_or_seq_string<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error
 }
 
 impl Doc{
-    pub fn complete(&mut self) -> Result<(), Error>{
+    /// Fills in the audio fields from the archive metadata endpoint.
+    pub fn complete(&mut self, base_url: &str) -> Result<(), Error>{
         let identifier = &self.identifier;
-        let url = format!("{}/download/{identifier}/{identifier}_files.xml",
-            BASE_URL, identifier=identifier);
+        let url = format!("{}/metadata/{identifier}", base_url, identifier=identifier);
         info!("url: {}", url);
-        let response = ureq::get(&url).call()?;
+        let response = ureq::get(&url)
+            .set("Accept", "application/json")
+            .call()?;
         if response.status() != 200{
             let status_code = response.status();
-            let message = response.into_string().unwrap();
-            let error_message = format!("HTTP Error: {status_code}. Error: {message}");
-            error!("{error_message}");
-            return Err(Error::new(&error_message));
+            let message = response.into_string().unwrap_or_default();
+            let error = MetadataError::Http(format!("{status_code}. Error: {message}"));
+            error!("{error}");
+            return Err(error.into());
         }
         let content = response.into_string()?;
-        let pattern_init = Regex::new(r#"^\s+<file name=".*\.(mp3|m4a)" source="original">"#).unwrap();
-        let pattern_end = Regex::new(r#"^\s+</file>"#).unwrap();
-        let mut audio = false;
-        let mut mp3_metadata: Vec<String> = Vec::new();
-        for line in content.lines(){
-            if !audio && pattern_init.is_match(line){
-                audio = true;
-            }
-            if audio{
-                mp3_metadata.push(line.to_string());
-            }
-            if audio && pattern_end.is_match(line){
-                break;
-            }
-        }
-        let text = mp3_metadata.concat();
-        if !text.is_empty(){
-            debug!("Text: {}", &text);
-            let mtime = Self::get_value("mtime", &text).parse().unwrap();
-            let size = Self::get_value("size", &text).parse().unwrap();
-            let length = Self::get_value("length", &text);
-            let length = match length.find('.'){
-                Some(pos) => length.get(0..pos).unwrap().parse().unwrap(),
-                None => length.parse().unwrap(),
-            };
-            let pattern = r#"<file name="([^"]*)" source="original">"#;
-            let re = Regex::new(pattern).unwrap();
-            let caps = re.captures(&text).unwrap();
-            let filename = caps.get(1).unwrap().as_str().to_string();
-            self.filename = filename;
-            self.mtime = mtime;
-            self.size = size;
-            self.length = length;
-        }
+        let audios = parse_audio_files(identifier, &content)?;
+        debug!("Audios: {:?}", &audios);
+        let audio = &audios[0];
+        self.filename = audio.name.clone();
+        self.mtime = audio.mtime;
+        self.size = audio.size;
+        self.length = audio.length;
+        self.audios = audios;
         info!("complete doc");
         Ok(())
     }
@@ -196,8 +173,12 @@ impl Doc{
         self.length
     }
 
-    pub async fn exists(&self) -> bool{
-        let file = format!("episodes/{}", self.get_post_filename());
+    pub fn get_audios(&self) -> &[AudioFile] {
+        &self.audios
+    }
+
+    pub async fn exists(&self, directory: &str) -> bool{
+        let file = format!("{}/{}", directory, self.get_post_filename());
         match fs::metadata(&file).await{
             Ok(metadata) => {
                 debug!("Output file {} exists", &file);
@@ -209,28 +190,51 @@ impl Doc{
             },
         }
     }
+}
 
-    fn get(tag: &str, xml: &str) -> Vec<String>{
-        let mut result = Vec::new();
-        let pattern = format!("<{tag}>([^<]*)</{tag}>", tag=tag);
-        let re = Regex::new(&pattern).unwrap();
-
-        for cap in re.captures_iter(xml){
-            result.push(cap.get(1).unwrap().as_str().to_string());
-        }
-        result
-    }
-    fn get_value(tag: &str, xml: &str) -> String{
-        let value = Self::get(tag, xml);
-        if !value.is_empty(){
-            match value.first() {
-                Some(value) => value.to_string(),
-                None => "".to_string()
-            }
-        }else{
-            "".to_string()
-        }
-
+#[cfg(test)]
+mod tests {
+    use mockito::Server;
+    use super::Doc;
+
+    #[test]
+    fn test_complete(){
+        let mut server = Server::new();
+        let mock = server.mock("GET", "/metadata/prueba-2")
+            .with_body_from_file("tests/fixtures/prueba-2_metadata.json")
+            .create();
+        let mut doc: Doc = serde_json::from_value(serde_json::json!({
+            "identifier": "prueba-2",
+            "publicdate": "2024-01-09T10:00:00Z",
+            "subject": "rust",
+            "description": "Segundo episodio de prueba",
+            "title": "Prueba 2",
+            "downloads": 3,
+        })).unwrap();
+        doc.complete(&server.url()).unwrap();
+        mock.assert();
+        assert_eq!(doc.get_audio_filename(), "prueba-2.mp3");
+        assert_eq!(doc.get_size(), 9876543);
+        assert_eq!(doc.get_length(), 1234);
+        assert_eq!(doc.get_mtime(), 1704794400);
+        assert_eq!(doc.get_audios().len(), 3);
+    }
+
+    #[test]
+    fn test_complete_invalid(){
+        let mut server = Server::new();
+        server.mock("GET", "/metadata/prueba-2")
+            .with_body(r#"{"files": [{"name": "prueba-2.mp3", "source": "original", "size": "?"}]}"#)
+            .create();
+        let mut doc: Doc = serde_json::from_value(serde_json::json!({
+            "identifier": "prueba-2",
+            "publicdate": "2024-01-09T10:00:00Z",
+            "subject": "rust",
+            "description": "Segundo episodio de prueba",
+            "title": "Prueba 2",
+            "downloads": 3,
+        })).unwrap();
+        assert!(doc.complete(&server.url()).is_err());
+        assert!(doc.get_audio_filename().is_empty());
     }
 }
-
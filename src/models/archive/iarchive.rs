@@ -1,8 +1,12 @@
 use tracing::{debug, error};
 use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Utc};
 use super::{
     Doc,
-    BASE_URL,
+    ItemMetadata,
+    default_base_url,
+    default_s3_url,
+    super::error::Error,
 };
 
 const PAGESIZE: usize = 200;
@@ -11,6 +15,10 @@ const PAGESIZE: usize = 200;
 pub struct IAClient{
     pub uploader: String,
     pub podcast: String,
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+    #[serde(default = "default_s3_url")]
+    pub s3_url: String,
 }
 
 impl IAClient{
@@ -18,16 +26,76 @@ impl IAClient{
         Self{
             uploader: uploader.to_string(),
             podcast: podcast.to_string(),
+            base_url: default_base_url(),
+            s3_url: default_s3_url(),
         }
     }
 
-    pub fn get_all_docs(&self) -> Vec<Doc>{
+    pub fn with_base_url(mut self, base_url: &str) -> Self{
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn get_s3_url(&self) -> &str{
+        self.s3_url.trim_end_matches('/')
+    }
+
+    pub fn get_base_url(&self) -> &str{
+        self.base_url.trim_end_matches('/')
+    }
+
+    /// Base url without the scheme, as the templates append it to the op3
+    /// prefix.
+    pub fn get_host(&self) -> &str{
+        let base_url = self.get_base_url();
+        base_url.split_once("://")
+            .map(|(_, host)| host)
+            .unwrap_or(base_url)
+    }
+
+    pub fn get_download_url(&self, identifier: &str, filename: &str) -> String{
+        format!("{}/download/{}/{}", self.get_base_url(), identifier, filename)
+    }
+
+    /// Returns the editable metadata of the item `identifier`.
+    pub fn get_item_metadata(&self, identifier: &str) -> Result<ItemMetadata, Error>{
+        let url = format!("{}/metadata/{}", self.get_base_url(), identifier);
+        debug!("url: {}", url);
+        let content = ureq::get(&url)
+            .set("Accept", "application/json")
+            .call()?
+            .into_string()?;
+        ItemMetadata::from_json(&content)
+            .map_err(|e| Error::new(&format!("Can not parse metadata of {identifier}: {e}")))
+    }
+
+    pub fn get_all_docs(&self) -> Result<Vec<Doc>, Error>{
         let since = "1970-01-01";
         let page = 1;
         self.get_docs(since, page)
     }
 
-    fn get_docs(&self, since: &str, page: usize) -> Vec<Doc>{
+    /// Returns the docs published after `since`, numbered after
+    /// `last_number`.
+    pub fn get_new_docs(&self, since: &DateTime<Utc>, last_number: usize)
+            -> Result<Vec<Doc>, Error>{
+        let since_day = since.format("%Y-%m-%d").to_string();
+        let mut docs: Vec<Doc> = self.get_docs(&since_day, 1)?
+            .into_iter()
+            .filter(|doc| doc.get_datetime() > *since)
+            .collect();
+        docs.sort_by_key(|doc| doc.get_datetime());
+        for (i, doc) in docs.iter_mut().enumerate(){
+            doc.set_number(last_number + i + 1);
+        }
+        docs.sort_by_key(|b| std::cmp::Reverse(b.get_datetime()));
+        Ok(docs)
+    }
+
+    /// Returns the docs published since the day `since`, from `page` on. A
+    /// failed request or an unexpected response is an error, so that a
+    /// partial list is never taken as the whole one.
+    fn get_docs(&self, since: &str, page: usize) -> Result<Vec<Doc>, Error>{
         let mut items = Vec::new();
         let q: String = [
             format!("uploader:({uploader})", uploader=self.uploader),
@@ -53,65 +121,50 @@ impl IAClient{
             ("rows", pagesize_str.as_str()),
             ("page", page_str.as_str()),
         ];
-        let url = format!("{BASE_URL}/advancedsearch.php");
-        match ureq::get(&url)
+        let url = format!("{}/advancedsearch.php", self.get_base_url());
+        let json: serde_json::Value = ureq::get(&url)
             .query_pairs(query_pairs)
             .set("Accept", "application/json")
-            .call(){
-            Ok(response) => {
-                if response.status() == 200 {
-                    match response.into_json::<serde_json::Value>(){
-                        Ok(json) => {
-                            let response = &json["response"];
-                            let num_found = response["numFound"].as_u64().unwrap();
-                            let start = response["start"].as_u64().unwrap();
-                            debug!("Page: {}", page);
-                            debug!("Start: {}", start);
-                            debug!("Found: {}", num_found);
-                            let pagesize: u64 = PAGESIZE.try_into().unwrap();
-                            if num_found > start + pagesize {
-                                debug!("Recursion");
-                                let new_page = page + 1;
-                                debug!("Page: {}", new_page);
-                                let mut more_items = self.get_docs(since, new_page);
-                                items.append(&mut more_items)
-                            }
-                            for (i, doc) in response["docs"].as_array().unwrap().iter().enumerate(){
-                                debug!("Doc: {:?}", doc);
-                                debug!("=============");
-                                let mut doc: Doc = match serde_json::from_value(doc.clone()){
-                                    Ok(doc) => {
-                                        debug!("Got doc");
-                                        doc
-                                    },
-                                    Err(e) => {
-                                        error!("Error: {e}");
-                                        continue
-                                    },
-                                };
-                                let number = i + 1 + (page - 1) * PAGESIZE;
-                                debug!("Doc {}. Number: {} => {}", doc.get_identifier(), i, number);
-                                //debug!("Doc {:?}", &doc);
-                                doc.set_number(number);
-                                items.push(doc);
-                            }
-                        },
-                        Err(e) => {
-                            error!("Cant get response: {e}");
-                        }
-                    }
-                }else{
-                    let status_code = response.status();
-                    let message = response.into_string().unwrap();
-                    error!("HTTP Error: {status_code}. Error: {message}");
-                }
-            },
-            Err(e) => {
-                error!("Cant get response: {e}");
-            }
+            .call()?
+            .into_json()?;
+        let response = &json["response"];
+        let (Some(num_found), Some(start), Some(docs)) = (
+                response["numFound"].as_u64(),
+                response["start"].as_u64(),
+                response["docs"].as_array()) else {
+            return Err(Error::new("Unexpected response of Internet Archive search"));
+        };
+        debug!("Page: {}", page);
+        debug!("Start: {}", start);
+        debug!("Found: {}", num_found);
+        let pagesize: u64 = PAGESIZE.try_into().unwrap();
+        if num_found > start + pagesize {
+            debug!("Recursion");
+            let new_page = page + 1;
+            debug!("Page: {}", new_page);
+            let mut more_items = self.get_docs(since, new_page)?;
+            items.append(&mut more_items)
+        }
+        for (i, doc) in docs.iter().enumerate(){
+            debug!("Doc: {:?}", doc);
+            debug!("=============");
+            let mut doc: Doc = match serde_json::from_value(doc.clone()){
+                Ok(doc) => {
+                    debug!("Got doc");
+                    doc
+                },
+                Err(e) => {
+                    error!("Error: {e}");
+                    continue
+                },
+            };
+            let number = i + 1 + (page - 1) * PAGESIZE;
+            debug!("Doc {}. Number: {} => {}", doc.get_identifier(), i, number);
+            doc.set_number(number);
+            items.push(doc);
         }
         items.sort_by_key(|b| std::cmp::Reverse(b.get_datetime()));
-        items
+        Ok(items)
     }
 }
 
@@ -124,6 +177,7 @@ mod tests {
     };
     use std::str::FromStr;
     use tracing::debug;
+    use mockito::{Server, Matcher};
     use super::IAClient;
 
 
@@ -132,13 +186,65 @@ mod tests {
         tracing_subscriber::registry()
             .with(EnvFilter::from_str("debug").unwrap())
             .with(tracing_subscriber::fmt::layer())
-            .init();
+            .try_init()
+            .ok();
 
-        let iaclient = IAClient::new( "atareao", "prueba");
-        let docs = iaclient.get_docs("1970-01-01", 1);
+        let mut server = Server::new();
+        let mock = server.mock("GET", "/advancedsearch.php")
+            .match_query(Matcher::UrlEncoded("page".into(), "1".into()))
+            .with_header("content-type", "application/json")
+            .with_body_from_file("tests/fixtures/advancedsearch.json")
+            .create();
+        let iaclient = IAClient::new( "atareao", "prueba")
+            .with_base_url(&server.url());
+        let docs = iaclient.get_docs("1970-01-01", 1).unwrap();
         if !docs.is_empty(){
             debug!("{:?}", docs.first().unwrap());
         }
-        assert!(!docs.is_empty())
+        mock.assert();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs.first().unwrap().get_identifier(), "prueba-2");
+        assert_eq!(docs.first().unwrap().get_number(), 2);
+    }
+
+    #[test]
+    fn test_get_new_docs(){
+        let mut server = Server::new();
+        let mock = server.mock("GET", "/advancedsearch.php")
+            .match_query(Matcher::UrlEncoded("q".into(),
+                "uploader:(atareao) AND publicdate:[2024-01-02 TO 9999-12-31] AND podcast:(prueba) AND mediatype:(audio)".into()))
+            .with_body_from_file("tests/fixtures/advancedsearch.json")
+            .create();
+        let iaclient = IAClient::new("atareao", "prueba")
+            .with_base_url(&server.url());
+        let since = "2024-01-02T10:00:00Z".parse().unwrap();
+        let docs = iaclient.get_new_docs(&since, 41).unwrap();
+        mock.assert();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].get_identifier(), "prueba-2");
+        assert_eq!(docs[0].get_number(), 42);
+    }
+
+    #[test]
+    fn test_get_docs_error(){
+        let mut server = Server::new();
+        let mock = server.mock("GET", "/advancedsearch.php")
+            .match_query(Matcher::Any)
+            .with_status(503)
+            .create();
+        let iaclient = IAClient::new("atareao", "prueba")
+            .with_base_url(&server.url());
+        assert!(iaclient.get_all_docs().is_err());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_get_host(){
+        let iaclient = IAClient::new("atareao", "prueba");
+        assert_eq!(iaclient.get_host(), "archive.org");
+        let iaclient = iaclient.with_base_url("http://127.0.0.1:8000/");
+        assert_eq!(iaclient.get_host(), "127.0.0.1:8000");
+        assert_eq!(iaclient.get_download_url("prueba-1", "prueba.mp3"),
+            "http://127.0.0.1:8000/download/prueba-1/prueba.mp3");
     }
 }
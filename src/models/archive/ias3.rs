@@ -0,0 +1,203 @@
+use tracing::{info, debug, error};
+use std::{fs::File, path::Path};
+
+use super::{
+    IAClient,
+    MetadataDiff,
+    super::{
+        draft::Draft,
+        error::Error,
+        utils::encode,
+    },
+};
+
+const DEFAULT_COLLECTION: &str = "opensource_audio";
+
+pub fn get_ias3_client(s3_url: &str) -> Option<IAS3>{
+    match std::env::var("IA_ACCESS_KEY"){
+        Ok(access_key) => {
+            match std::env::var("IA_SECRET_KEY"){
+                Ok(secret_key) => Some(IAS3::new(&access_key, &secret_key, s3_url)),
+                Err(_) => None,
+            }
+        },
+        Err(_) => None,
+    }
+}
+
+/// Client for the write APIs of Internet Archive: the S3-like one to create
+/// items and the metadata one to edit them.
+pub struct IAS3{
+    access_key: String,
+    secret_key: String,
+    url: String,
+}
+
+impl IAS3{
+    pub fn new(access_key: &str, secret_key: &str, url: &str) -> Self{
+        Self{
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Creates the item described by `draft` with `audio` as its file, with
+    /// the metadata that `IAClient` looks for. Returns the identifier.
+    pub fn upload(&self, iaclient: &IAClient, draft: &Draft, audio: &str) -> Result<String, Error>{
+        let identifier = draft.get_identifier();
+        let filename = Path::new(audio)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Error::new(&format!("Invalid audio file {audio}")))?;
+        let url = format!("{}/{}/{}", self.url, identifier, encode(filename));
+        info!("url: {}", url);
+        let file = File::open(audio)?;
+        let size = file.metadata()?.len();
+        let mut request = ureq::put(&url)
+            .set("Authorization", &format!("LOW {}:{}", self.access_key, self.secret_key))
+            .set("Content-Length", &size.to_string())
+            .set("x-amz-auto-make-bucket", "1")
+            .set("x-archive-size-hint", &size.to_string());
+        for (name, value) in Self::get_headers(iaclient, draft){
+            debug!("{}: {}", name, value);
+            request = request.set(&name, &value);
+        }
+        let response = request.send(file)?;
+        if response.status() != 200{
+            let status_code = response.status();
+            let message = response.into_string().unwrap_or_default();
+            let error_message = format!("HTTP Error: {status_code}. Error: {message}");
+            error!("{error_message}");
+            return Err(Error::new(&error_message));
+        }
+        info!("Uploaded {} to {}", audio, identifier);
+        Ok(identifier)
+    }
+
+    /// Submits the changes of `diff` to the metadata write API of the
+    /// archive at `base_url`.
+    pub fn patch_metadata(&self, base_url: &str, diff: &MetadataDiff) -> Result<(), Error>{
+        let url = format!("{}/metadata/{}", base_url.trim_end_matches('/'), diff.identifier);
+        info!("url: {}", url);
+        let patch = diff.to_patch().to_string();
+        debug!("Patch: {}", patch);
+        let response = ureq::post(&url)
+            .set("Accept", "application/json")
+            .send_form(&[
+                ("-target", "metadata"),
+                ("-patch", &patch),
+                ("access", &self.access_key),
+                ("secret", &self.secret_key),
+            ])?;
+        let json: serde_json::Value = response.into_json()?;
+        if json["success"].as_bool() != Some(true){
+            let error_message = format!("Can not patch {}. {}", diff.identifier, json["error"]);
+            error!("{error_message}");
+            return Err(Error::new(&error_message));
+        }
+        info!("Patched {}", diff.identifier);
+        Ok(())
+    }
+
+    fn get_headers(iaclient: &IAClient, draft: &Draft) -> Vec<(String, String)>{
+        let metadata = &draft.metadata;
+        let collection = metadata.collection.as_deref().unwrap_or(DEFAULT_COLLECTION);
+        let mut headers = vec![
+            ("x-archive-meta-mediatype".to_string(), "audio".to_string()),
+            ("x-archive-meta-collection".to_string(), header_value(collection)),
+            ("x-archive-meta-title".to_string(), header_value(&metadata.title)),
+            ("x-archive-meta-podcast".to_string(), header_value(&iaclient.podcast)),
+            ("x-archive-meta-uploader".to_string(), header_value(&iaclient.uploader)),
+            ("x-archive-meta-description".to_string(), header_value(&draft.content)),
+        ];
+        if let Some(date) = &metadata.date{
+            headers.push(("x-archive-meta-date".to_string(), header_value(date)));
+        }
+        for (i, subject) in metadata.subject.iter().enumerate(){
+            headers.push((format!("x-archive-meta{:02}-subject", i + 1), header_value(subject)));
+        }
+        headers
+    }
+}
+
+/// Header values that are not plain ASCII go as `uri(...)`, the way IAS3
+/// expects them.
+fn header_value(value: &str) -> String{
+    if value.chars().all(|c| c.is_ascii() && !c.is_ascii_control()){
+        value.to_string()
+    }else{
+        format!("uri({})", encode(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{Server, Matcher};
+    use super::{IAS3, IAClient, Draft, MetadataDiff};
+    use super::super::{ItemMetadata, super::draft::DraftMetadata};
+
+    #[test]
+    fn test_patch_metadata(){
+        let mut server = Server::new();
+        let mock = server.mock("POST", "/metadata/prueba-2")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("-target".into(), "metadata".into()),
+                Matcher::UrlEncoded("-patch".into(),
+                    r#"[{"op":"replace","path":"/title","value":"Prueba dos"}]"#.into()),
+                Matcher::UrlEncoded("access".into(), "access".into()),
+                Matcher::UrlEncoded("secret".into(), "secret".into()),
+            ]))
+            .with_body(r#"{"success": true, "task_id": 1}"#)
+            .create();
+        let remote = ItemMetadata{
+            title: Some("Prueba 2".to_string()),
+            subject: vec!["rust".to_string()],
+            description: None,
+        };
+        let local = ItemMetadata{
+            title: Some("Prueba dos".to_string()),
+            ..remote.clone()
+        };
+        let diff = MetadataDiff::new("prueba-2", &remote, &local);
+        let ias3 = IAS3::new("access", "secret", "http://localhost");
+        ias3.patch_metadata(&server.url(), &diff).unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_upload(){
+        let mut server = Server::new();
+        let mock = server.mock("PUT", "/prueba-3/prueba%203.mp3")
+            .match_header("authorization", "LOW access:secret")
+            .match_header("x-amz-auto-make-bucket", "1")
+            .match_header("x-archive-meta-mediatype", "audio")
+            .match_header("x-archive-meta-title", "uri(Prueba%203%20con%20%C3%B1)")
+            .match_header("x-archive-meta-podcast", "prueba")
+            .match_header("x-archive-meta-uploader", "atareao")
+            .match_header("x-archive-meta01-subject", "rust")
+            .match_header("x-archive-meta02-subject", "neovim")
+            .match_header("x-archive-meta-description", "uri(Primera%20l%C3%ADnea%0ASegunda)")
+            .match_body(Matcher::Exact("audio".to_string()))
+            .create();
+        let dir = std::env::temp_dir().join("iapodcast-test-upload");
+        std::fs::create_dir_all(&dir).unwrap();
+        let audio = dir.join("prueba 3.mp3");
+        std::fs::write(&audio, "audio").unwrap();
+        let draft = Draft{
+            metadata: DraftMetadata{
+                title: "Prueba 3 con ñ".to_string(),
+                subject: vec!["rust".to_string(), "neovim".to_string()],
+                identifier: Some("prueba-3".to_string()),
+                date: None,
+                collection: None,
+            },
+            content: "Primera línea\nSegunda".to_string(),
+        };
+        let iaclient = IAClient::new("atareao", "prueba");
+        let ias3 = IAS3::new("access", "secret", &server.url());
+        let identifier = ias3.upload(&iaclient, &draft, audio.to_str().unwrap()).unwrap();
+        mock.assert();
+        assert_eq!(identifier, "prueba-3");
+    }
+}
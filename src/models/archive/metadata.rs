@@ -0,0 +1,200 @@
+use serde::{Serialize, Deserialize};
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display},
+};
+
+use super::super::error::Error;
+
+/// Extensions of the audio files, in order of preference for the main
+/// enclosure.
+const AUDIO_EXTENSIONS: [&str; 5] = ["mp3", "m4a", "ogg", "opus", "flac"];
+
+/// Errors reading the metadata of an Internet Archive item.
+#[derive(Debug)]
+pub enum MetadataError{
+    Http(String),
+    Parse(String),
+    InvalidField{
+        file: String,
+        field: &'static str,
+        value: String,
+    },
+    NoAudio(String),
+}
+
+impl Display for MetadataError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            MetadataError::Http(message) => write!(f, "HTTP Error: {message}"),
+            MetadataError::Parse(message) => write!(f, "Can not parse metadata: {message}"),
+            MetadataError::InvalidField{file, field, value} =>
+                write!(f, "Invalid {field} `{value}` for file {file}"),
+            MetadataError::NoAudio(identifier) =>
+                write!(f, "No original audio file in {identifier}"),
+        }
+    }
+}
+
+impl StdError for MetadataError {}
+
+impl From<MetadataError> for Error{
+    fn from(error: MetadataError) -> Self{
+        Error::new(&error.to_string())
+    }
+}
+
+/// File as listed by the archive metadata endpoint. Every value is a
+/// string there.
+#[derive(Debug, Deserialize)]
+struct RawFile{
+    name: String,
+    source: String,
+    format: Option<String>,
+    mtime: Option<String>,
+    size: Option<String>,
+    length: Option<String>,
+    md5: Option<String>,
+    sha1: Option<String>,
+    bitrate: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMetadata{
+    #[serde(default)]
+    files: Vec<RawFile>,
+}
+
+/// Audio file of an item, either the original upload or a derivative
+/// rendition made by the archive.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, PartialOrd)]
+pub struct AudioFile{
+    pub name: String,
+    pub source: String,
+    pub format: String,
+    pub mtime: u64,
+    pub size: u64,
+    /// Duration in seconds
+    pub length: u64,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    /// Bitrate in kbps
+    pub bitrate: Option<u64>,
+}
+
+impl AudioFile{
+    pub fn is_original(&self) -> bool{
+        self.source == "original"
+    }
+
+    fn from_raw(raw: RawFile) -> Result<Self, MetadataError>{
+        let mtime = parse_u64(&raw.name, "mtime", raw.mtime.as_deref())?;
+        let size = parse_u64(&raw.name, "size", raw.size.as_deref())?;
+        let length = match raw.length.as_deref(){
+            Some(value) => parse_length(value).ok_or_else(|| MetadataError::InvalidField{
+                file: raw.name.clone(),
+                field: "length",
+                value: value.to_string(),
+            })?,
+            None => 0,
+        };
+        let bitrate = match raw.bitrate.as_deref(){
+            Some(value) => Some(parse_u64(&raw.name, "bitrate", Some(value))?),
+            None => None,
+        };
+        Ok(Self{
+            format: raw.format.unwrap_or_default(),
+            name: raw.name,
+            source: raw.source,
+            mtime,
+            size,
+            length,
+            md5: raw.md5,
+            sha1: raw.sha1,
+            bitrate,
+        })
+    }
+}
+
+/// Parses the answer of `/metadata/{identifier}` and returns its audio
+/// files. The original ones come first, the preferred one for the main
+/// enclosure at the top, followed by the derivative renditions.
+pub fn parse_audio_files(identifier: &str, json: &str) -> Result<Vec<AudioFile>, MetadataError>{
+    let metadata: RawMetadata = serde_json::from_str(json)
+        .map_err(|e| MetadataError::Parse(e.to_string()))?;
+    let mut audios = Vec::new();
+    for raw in metadata.files{
+        if (raw.source == "original" || raw.source == "derivative")
+                && get_priority(&raw.name).is_some(){
+            audios.push(AudioFile::from_raw(raw)?);
+        }
+    }
+    if !audios.iter().any(AudioFile::is_original){
+        return Err(MetadataError::NoAudio(identifier.to_string()));
+    }
+    audios.sort_by_key(|audio| (!audio.is_original(), get_priority(&audio.name)));
+    Ok(audios)
+}
+
+fn get_priority(name: &str) -> Option<usize>{
+    let extension = name.rsplit_once('.')?.1.to_lowercase();
+    AUDIO_EXTENSIONS.iter().position(|e| *e == extension)
+}
+
+fn parse_u64(file: &str, field: &'static str, value: Option<&str>) -> Result<u64, MetadataError>{
+    match value{
+        Some(value) => value.trim().parse().map_err(|_| MetadataError::InvalidField{
+            file: file.to_string(),
+            field,
+            value: value.to_string(),
+        }),
+        None => Ok(0),
+    }
+}
+
+/// Parses a duration given either in seconds (`1234.56`) or as
+/// `[hh:]mm:ss`, dropping the fraction of second.
+fn parse_length(value: &str) -> Option<u64>{
+    let mut seconds = 0.0;
+    for part in value.trim().split(':'){
+        let part: f64 = part.parse().ok()?;
+        if !part.is_finite() || part < 0.0{
+            return None;
+        }
+        seconds = seconds * 60.0 + part;
+    }
+    Some(seconds as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_audio_files, parse_length, MetadataError};
+
+    #[test]
+    fn test_parse_audio_files(){
+        let json = std::fs::read_to_string("tests/fixtures/prueba-2_metadata.json").unwrap();
+        let audios = parse_audio_files("prueba-2", &json).unwrap();
+        assert_eq!(audios.len(), 3);
+        assert_eq!(audios[0].name, "prueba-2.mp3");
+        assert_eq!(audios[0].length, 1234);
+        assert_eq!(audios[0].bitrate, Some(128));
+        assert_eq!(audios[1].name, "prueba-2.opus");
+        assert_eq!(audios[1].length, 1234);
+        assert_eq!(audios[1].sha1.as_deref(), Some("e9d71f5ee7c92d6dc9e92ffdad17b8bd49418f98"));
+        assert_eq!(audios[2].name, "prueba-2.ogg");
+        assert!(!audios[2].is_original());
+    }
+
+    #[test]
+    fn test_invalid_metadata(){
+        let json = r#"{"files": [{"name": "a.mp3", "source": "original", "size": "big"}]}"#;
+        assert!(matches!(parse_audio_files("a", json),
+            Err(MetadataError::InvalidField{field: "size", ..})));
+        let json = r#"{"files": [{"name": "a.png", "source": "original"},
+            {"name": "a.ogg", "source": "derivative"}]}"#;
+        assert!(matches!(parse_audio_files("a", json), Err(MetadataError::NoAudio(_))));
+        assert_eq!(parse_length("20:34.5"), Some(1234));
+        assert_eq!(parse_length("1:00:01"), Some(3601));
+        assert_eq!(parse_length("abc"), None);
+    }
+}
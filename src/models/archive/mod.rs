@@ -1,7 +1,24 @@
 mod iarchive;
 mod doc;
+mod ias3;
+mod metadata;
+mod patch;
+mod state;
 
 pub use doc::Doc;
+pub use metadata::AudioFile;
+pub use patch::{ItemMetadata, MetadataDiff};
 pub use iarchive::IAClient;
+pub use ias3::get_ias3_client;
+pub use state::SyncState;
 
 const BASE_URL: &str = "https://archive.org";
+const S3_URL: &str = "https://s3.us.archive.org";
+
+fn default_base_url() -> String {
+    BASE_URL.to_string()
+}
+
+fn default_s3_url() -> String {
+    S3_URL.to_string()
+}
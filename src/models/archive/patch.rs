@@ -0,0 +1,149 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt::{self, Display};
+
+use super::super::utils::string_or_seq_string;
+
+/// Fields of an item that can be edited locally.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ItemMetadata{
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "string_or_seq_string")]
+    pub subject: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawItem{
+    metadata: Option<ItemMetadata>,
+}
+
+impl ItemMetadata{
+    /// Parses the answer of `/metadata/{identifier}`.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error>{
+        let item: RawItem = serde_json::from_str(json)?;
+        item.metadata.ok_or_else(|| serde::de::Error::custom("item without metadata"))
+    }
+}
+
+/// Change of a field between the remote item and the local episode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataChange{
+    pub field: &'static str,
+    pub remote: Option<Value>,
+    pub local: Value,
+}
+
+/// Differences of an episode with its item in Internet Archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataDiff{
+    pub identifier: String,
+    pub changes: Vec<MetadataChange>,
+}
+
+impl MetadataDiff{
+    pub fn new(identifier: &str, remote: &ItemMetadata, local: &ItemMetadata) -> Self{
+        let mut changes = Vec::new();
+        if let Some(title) = &local.title{
+            if remote.title.as_deref().map(str::trim) != Some(title.trim()){
+                changes.push(MetadataChange{
+                    field: "title",
+                    remote: remote.title.clone().map(Value::from),
+                    local: Value::from(title.trim()),
+                });
+            }
+        }
+        if remote.subject != local.subject{
+            changes.push(MetadataChange{
+                field: "subject",
+                remote: if remote.subject.is_empty() { None } else { Some(json!(remote.subject)) },
+                local: json!(local.subject),
+            });
+        }
+        if let Some(description) = &local.description{
+            if remote.description.as_deref().map(str::trim) != Some(description.trim()){
+                changes.push(MetadataChange{
+                    field: "description",
+                    remote: remote.description.clone().map(Value::from),
+                    local: Value::from(description.trim()),
+                });
+            }
+        }
+        Self{
+            identifier: identifier.to_string(),
+            changes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.changes.is_empty()
+    }
+
+    /// JSON Patch accepted by the metadata write API.
+    pub fn to_patch(&self) -> Value{
+        Value::Array(self.changes.iter()
+            .map(|change| json!({
+                "op": if change.remote.is_some() { "replace" } else { "add" },
+                "path": format!("/{}", change.field),
+                "value": change.local,
+            }))
+            .collect())
+    }
+}
+
+impl Display for MetadataDiff{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.identifier)?;
+        for change in &self.changes{
+            writeln!(f, "  {}:", change.field)?;
+            if let Some(remote) = &change.remote{
+                for line in to_text(remote).lines(){
+                    writeln!(f, "    - {}", line)?;
+                }
+            }
+            for line in to_text(&change.local).lines(){
+                writeln!(f, "    + {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn to_text(value: &Value) -> String{
+    match value{
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter()
+            .map(to_text)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::{ItemMetadata, MetadataDiff};
+
+    #[test]
+    fn test_metadata_diff(){
+        let json = std::fs::read_to_string("tests/fixtures/prueba-2_metadata.json").unwrap();
+        let remote = ItemMetadata::from_json(&json).unwrap();
+        let local = ItemMetadata{
+            title: Some("Prueba 2".to_string()),
+            subject: vec!["rust".to_string(), "neovim".to_string()],
+            description: Some("Nueva descripción".to_string()),
+        };
+        let diff = MetadataDiff::new("prueba-2", &remote, &local);
+        assert_eq!(diff.changes.len(), 2);
+        assert_eq!(diff.to_patch(), json!([
+            {"op": "replace", "path": "/subject", "value": ["rust", "neovim"]},
+            {"op": "add", "path": "/description", "value": "Nueva descripción"},
+        ]));
+        let text = diff.to_string();
+        assert!(text.contains("    - rust\n    + rust, neovim"));
+        assert!(MetadataDiff::new("prueba-2", &remote, &remote).is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Duration, Utc};
+use tracing::{debug, error};
+
+/// Cursor of the last sync with Internet Archive.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SyncState{
+    /// Newest `publicdate` seen
+    pub last_publicdate: Option<DateTime<Utc>>,
+    /// Number of the newest doc seen
+    #[serde(default)]
+    pub last_number: usize,
+    pub last_sync: Option<DateTime<Utc>>,
+    pub last_full_sync: Option<DateTime<Utc>>,
+}
+
+impl SyncState{
+    /// Reads the state from `path`, or an empty one if it does not exist.
+    pub async fn read(path: &str) -> Self{
+        match tokio::fs::read_to_string(path).await{
+            Ok(content) => match serde_yaml::from_str(&content){
+                Ok(state) => state,
+                Err(e) => {
+                    error!("Can not parse sync state {path}: {e}");
+                    Self::default()
+                }
+            },
+            Err(e) => {
+                debug!("Can not read sync state {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    pub async fn save(&self, path: &str) -> tokio::io::Result<()>{
+        tokio::fs::write(path, serde_yaml::to_string(self).unwrap()).await
+    }
+
+    /// A full sync is needed when there is no cursor or the last full sync
+    /// is older than `full_refresh_days`.
+    pub fn needs_full_sync(&self, full_refresh_days: i64, now: DateTime<Utc>) -> bool{
+        match (self.last_publicdate, self.last_full_sync){
+            (Some(_), Some(last_full_sync)) =>
+                now - last_full_sync >= Duration::days(full_refresh_days),
+            _ => true,
+        }
+    }
+
+    /// Moves the cursor after a sync that saved docs with the given
+    /// `publicdate` and number. With a `failed` doc the cursor stops before
+    /// it, so that the next sync requests it again.
+    pub fn advance(&mut self, seen: &[(DateTime<Utc>, usize)], failed: Option<&DateTime<Utc>>,
+            full: bool, now: DateTime<Utc>){
+        let seen = seen.iter()
+            .filter(|(publicdate, _)| failed.map_or(true, |failed| publicdate < failed));
+        for (publicdate, number) in seen{
+            if self.last_publicdate.map_or(true, |last| *publicdate > last){
+                self.last_publicdate = Some(*publicdate);
+            }
+            if *number > self.last_number{
+                self.last_number = *number;
+            }
+        }
+        self.last_sync = Some(now);
+        if full{
+            self.last_full_sync = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use super::SyncState;
+
+    #[test]
+    fn test_sync_state(){
+        let now = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let mut state = SyncState::default();
+        assert!(state.needs_full_sync(7, now));
+        let first = Utc.with_ymd_and_hms(2024, 1, 2, 10, 0, 0).unwrap();
+        let second = Utc.with_ymd_and_hms(2024, 1, 9, 10, 0, 0).unwrap();
+        state.advance(&[(second, 2), (first, 1)], None, true, now);
+        assert_eq!(state.last_publicdate, Some(second));
+        assert_eq!(state.last_number, 2);
+        assert!(!state.needs_full_sync(7, now + Duration::days(6)));
+        assert!(state.needs_full_sync(7, now + Duration::days(7)));
+        state.advance(&[], None, false, now + Duration::days(1));
+        assert_eq!(state.last_full_sync, Some(now));
+        assert_eq!(state.last_publicdate, Some(second));
+        // The cursor stops before the first doc that could not be saved
+        let third = Utc.with_ymd_and_hms(2024, 1, 16, 10, 0, 0).unwrap();
+        let fourth = Utc.with_ymd_and_hms(2024, 1, 23, 10, 0, 0).unwrap();
+        let fifth = Utc.with_ymd_and_hms(2024, 1, 30, 10, 0, 0).unwrap();
+        state.advance(&[(fifth, 5), (third, 3)], Some(&fourth), false, now);
+        assert_eq!(state.last_publicdate, Some(third));
+        assert_eq!(state.last_number, 3);
+    }
+}
@@ -11,15 +11,74 @@ use std::{
 
 use super::{
     Podcast,
-    super::archive::IAClient};
+    SyncConfig,
+    MergeConfig,
+    FeedConfig,
+    FeedFormat,
+    TagsConfig,
+    IndexConfig,
+    RobotsConfig,
+    PublisherConfig,
+    PublisherKind,
+    super::{archive::IAClient, error::Error}};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Configuration{
     public: String,
     params: Option<Value>,
     assets: String,
+    #[serde(default = "default_episodes")]
+    episodes: String,
+    #[serde(default = "default_pages")]
+    pages: String,
+    #[serde(default = "default_templates")]
+    templates: String,
     podcast: Podcast,
     iaclient: IAClient,
+    sync: Option<SyncConfig>,
+    #[serde(default)]
+    merge: MergeConfig,
+    #[serde(default)]
+    feeds: Vec<FeedConfig>,
+    #[serde(default)]
+    tags: TagsConfig,
+    #[serde(default)]
+    index: IndexConfig,
+    #[serde(default)]
+    robots: RobotsConfig,
+    #[serde(default = "default_publishers")]
+    publishers: Vec<PublisherConfig>,
+    #[serde(default = "default_ledger")]
+    ledger: String,
+    #[serde(default = "default_manifest")]
+    manifest: String,
+}
+
+fn default_episodes() -> String {
+    "episodes".to_string()
+}
+
+fn default_pages() -> String {
+    "pages".to_string()
+}
+
+fn default_templates() -> String {
+    "templates".to_string()
+}
+
+fn default_ledger() -> String {
+    "announcements.yml".to_string()
+}
+
+fn default_manifest() -> String {
+    ".manifest.json".to_string()
+}
+
+fn default_publishers() -> Vec<PublisherConfig> {
+    vec![
+        PublisherConfig::new(PublisherKind::Telegram),
+        PublisherConfig::new(PublisherKind::Mastodon),
+    ]
 }
 
 impl Display for Configuration{
@@ -39,6 +98,56 @@ impl Configuration {
         &self.iaclient
     }
 
+    pub fn get_sync(&self) -> &Option<SyncConfig>{
+        &self.sync
+    }
+
+    pub fn get_merge(&self) -> &MergeConfig{
+        &self.merge
+    }
+
+    /// Feeds to generate. Only the RSS one in `podcast.feed_url` if none is
+    /// configured.
+    pub fn get_feeds(&self) -> Vec<FeedConfig>{
+        if self.feeds.is_empty(){
+            vec![FeedConfig{
+                format: FeedFormat::Rss,
+                path: self.podcast.feed_url.clone(),
+            }]
+        }else{
+            self.feeds.clone()
+        }
+    }
+
+    pub fn get_tags(&self) -> &TagsConfig{
+        &self.tags
+    }
+
+    pub fn get_index(&self) -> &IndexConfig{
+        &self.index
+    }
+
+    pub fn get_robots(&self) -> &RobotsConfig{
+        &self.robots
+    }
+
+    /// Publishers where the new episodes are announced. Telegram and
+    /// Mastodon if the section is missing.
+    pub fn get_publishers(&self) -> &[PublisherConfig]{
+        &self.publishers
+    }
+
+    /// File with the announcements made of every episode.
+    pub fn get_ledger(&self) -> &str{
+        &self.ledger
+    }
+
+    /// File with the hashes of the last build. It is out of the public
+    /// directory, so that it is not deployed.
+    pub fn get_manifest(&self) -> &str{
+        &self.manifest
+    }
+
     pub fn get_public(&self) -> &str{
         &self.public
     }
@@ -47,23 +156,60 @@ impl Configuration {
         &self.assets
     }
 
+    pub fn get_episodes(&self) -> &str{
+        &self.episodes
+    }
+
+    pub fn get_pages(&self) -> &str{
+        &self.pages
+    }
+
+    pub fn get_templates(&self) -> &str{
+        &self.templates
+    }
+
     pub fn get_params(&self) -> &Option<Value>{
         &self.params
     }
 
-    pub async fn read_configuration() -> Configuration{
-        let content = match read_to_string("config.yml")
-            .await {
-                Ok(value) => value,
-                Err(e) => {
-                    println!("Error with config file `config.yml`: {e}");
-                    process::exit(0);
-                }
-            };
-        match serde_yaml::from_str(&content){
+    pub fn set_public(&mut self, public: &str){
+        self.public = public.to_string();
+    }
+
+    pub fn set_manifest(&mut self, manifest: &str){
+        self.manifest = manifest.to_string();
+    }
+
+    pub fn set_episodes(&mut self, episodes: &str){
+        self.episodes = episodes.to_string();
+    }
+
+    pub fn set_pages(&mut self, pages: &str){
+        self.pages = pages.to_string();
+    }
+
+    pub fn set_templates(&mut self, templates: &str){
+        self.templates = templates.to_string();
+    }
+
+    /// Overrides the url of the site, used to build every link.
+    pub fn set_url(&mut self, url: &str){
+        self.podcast.url = url.to_string();
+    }
+
+    /// Reads the configuration in `path`.
+    pub async fn load(path: &str) -> Result<Configuration, Error>{
+        let content = read_to_string(path).await?;
+        let mut configuration = serde_yaml::from_str::<Configuration>(&content)?;
+        configuration.podcast.fill_guid();
+        Ok(configuration)
+    }
+
+    pub async fn read_configuration(path: &str) -> Configuration{
+        match Self::load(path).await{
             Ok(configuration) => configuration,
             Err(e) => {
-                println!("Error with config file `config.yml`: {e}");
+                println!("Error with config file `{path}`: {e}");
                 process::exit(0);
             }
         }
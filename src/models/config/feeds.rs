@@ -0,0 +1,18 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat{
+    /// RSS 2.0 with the iTunes and Podcasting 2.0 namespaces
+    Rss,
+    /// JSON Feed 1.1
+    Json,
+    Atom,
+}
+
+/// Feed to write in `path`, relative to the public directory.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FeedConfig{
+    pub format: FeedFormat,
+    pub path: String,
+}
@@ -0,0 +1,36 @@
+use serde::{Serialize, Deserialize};
+
+/// Pagination of the index, in `path/<number>/`, and the yearly archive, in
+/// `archive/<year>/`. With `per_page: 0`, the default, every episode goes in
+/// the index.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndexConfig{
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+    #[serde(default = "default_path")]
+    pub path: String,
+    #[serde(default = "default_archive")]
+    pub archive: String,
+}
+
+impl Default for IndexConfig{
+    fn default() -> Self{
+        Self{
+            per_page: default_per_page(),
+            path: default_path(),
+            archive: default_archive(),
+        }
+    }
+}
+
+fn default_per_page() -> usize {
+    0
+}
+
+fn default_path() -> String {
+    "page".to_string()
+}
+
+fn default_archive() -> String {
+    "archive".to_string()
+}
@@ -0,0 +1,31 @@
+use serde::{Serialize, Deserialize};
+
+/// What to do when a field of an episode differs from its item in Internet
+/// Archive.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MergePolicy{
+    /// Take the value from Internet Archive
+    Remote,
+    /// Keep the value of the front matter
+    #[default]
+    Local,
+    /// Keep the value of the front matter and report the difference
+    Conflict,
+}
+
+/// Merge policy for each field that comes from Internet Archive. Every
+/// field keeps the local value by default.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MergeConfig{
+    #[serde(default)]
+    pub title: MergePolicy,
+    #[serde(default)]
+    pub subject: MergePolicy,
+    #[serde(default)]
+    pub excerpt: MergePolicy,
+    #[serde(default)]
+    pub content: MergePolicy,
+    #[serde(default)]
+    pub slug: MergePolicy,
+}
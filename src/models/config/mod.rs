@@ -2,8 +2,24 @@ mod configuration;
 mod podcast;
 mod post;
 mod page;
+mod namespace;
+mod merge;
+mod sync;
+mod feeds;
+mod tags;
+mod index;
+mod robots;
+mod publishers;
 
 pub use configuration::Configuration;
 pub use podcast::Podcast;
-pub use post::Post;
+pub use post::{Post, Audio};
 pub use page::Page;
+pub use namespace::{Funding, Person, Transcript, Chapters, ValueBlock};
+pub use sync::SyncConfig;
+pub use merge::{MergeConfig, MergePolicy};
+pub use feeds::{FeedConfig, FeedFormat};
+pub use tags::TagsConfig;
+pub use index::IndexConfig;
+pub use robots::RobotsConfig;
+pub use publishers::{PublisherConfig, PublisherKind};
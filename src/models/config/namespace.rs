@@ -0,0 +1,85 @@
+//! Elements of the Podcasting 2.0 namespace,
+//! <https://podcastindex.org/namespace/1.0>.
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Funding{
+    pub url: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Person{
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub img: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Transcript{
+    pub url: String,
+    #[serde(rename = "type")]
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rel: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Chapters{
+    pub url: String,
+    #[serde(rename = "type", default = "default_chapters_type")]
+    pub mime_type: String,
+}
+
+fn default_chapters_type() -> String {
+    "application/json+chapters".to_string()
+}
+
+/// Value for value block, usually lightning payments split among the
+/// recipients.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ValueBlock{
+    #[serde(rename = "type", default = "default_value_type")]
+    pub kind: String,
+    #[serde(default = "default_value_method")]
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggested: Option<String>,
+    pub recipients: Vec<Recipient>,
+}
+
+fn default_value_type() -> String {
+    "lightning".to_string()
+}
+
+fn default_value_method() -> String {
+    "keysend".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Recipient{
+    pub name: String,
+    #[serde(rename = "type", default = "default_recipient_type")]
+    pub kind: String,
+    pub address: String,
+    pub split: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_value: Option<String>,
+    #[serde(default)]
+    pub fee: bool,
+}
+
+fn default_recipient_type() -> String {
+    "node".to_string()
+}
@@ -55,12 +55,19 @@ impl Page{
             length: 0,
             number: 0,
             downloads: 0,
+            mime_type: String::new(),
+            audios: Vec::new(),
+            season: None,
+            transcripts: Vec::new(),
+            chapters: None,
+            persons: Vec::new(),
+            value: None,
         }
     }
 
-    pub async fn new(filename: &str) -> Result<Self, serde_json::Error>{
+    pub async fn new(directory: &str, filename: &str) -> Result<Self, serde_json::Error>{
         info!("new: {filename}");
-        let filename = format!("pages/{}", filename);
+        let filename = format!("{}/{}", directory, filename);
         debug!("Filename: {}", filename);
         let data = tokio::fs::read_to_string(&filename)
             .await
@@ -96,7 +103,7 @@ mod tests {
             .with(tracing_subscriber::fmt::layer())
             .init();
 
-        let page = Page::new("about.md").await.unwrap();
+        let page = Page::new("pages", "about.md").await.unwrap();
         debug!("Title: {}", page.metadata.title);
         debug!("=========================");
         debug!("{:?}", page);
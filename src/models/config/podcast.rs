@@ -1,4 +1,10 @@
 use serde::{Serialize, Deserialize, Deserializer};
+use uuid::Uuid;
+
+use super::{Funding, Person, ValueBlock};
+
+/// Namespace of the `podcast:guid` values
+const PODCAST_NAMESPACE: Uuid = Uuid::from_u128(0xead4c236_bf58_58c6_a2c6_a6b28d128cb6);
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Podcast{
@@ -15,6 +21,50 @@ pub struct Podcast{
     pub description: String,
     pub keywords: Vec<String>,
     pub license: String,
+    #[serde(default = "default_language")]
+    pub language: String,
+    // Podcasting 2.0
+    #[serde(default)]
+    pub license_url: Option<String>,
+    /// `podcast:guid`. Computed from the feed url if empty
+    #[serde(default)]
+    pub guid: Option<String>,
+    #[serde(default)]
+    pub locked: bool,
+    #[serde(default)]
+    pub funding: Vec<Funding>,
+    #[serde(default)]
+    pub persons: Vec<Person>,
+    #[serde(default)]
+    pub value: Option<ValueBlock>,
+}
+
+impl Podcast{
+    pub fn get_feed_url(&self) -> String{
+        self.get_url(&self.feed_url)
+    }
+
+    /// Absolute url of `path` in the site.
+    pub fn get_url(&self, path: &str) -> String{
+        format!("{}/{}", self.url.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+
+    /// Fills in `guid` with the UUIDv5 of the feed url, without scheme nor
+    /// trailing slashes, as the namespace specifies.
+    pub fn fill_guid(&mut self){
+        if self.guid.is_none(){
+            let feed_url = self.get_feed_url();
+            let feed_url = feed_url.split_once("://")
+                .map(|(_, rest)| rest)
+                .unwrap_or(&feed_url)
+                .trim_end_matches('/');
+            self.guid = Some(Uuid::new_v5(&PODCAST_NAMESPACE, feed_url.as_bytes()).to_string());
+        }
+    }
+}
+
+fn default_language() -> String {
+    "es-es".to_string()
 }
 
 fn empty_as_none<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
@@ -28,3 +78,29 @@ where
         Ok(Some(s.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Podcast;
+
+    #[test]
+    fn test_fill_guid(){
+        let mut podcast: Podcast = serde_yaml::from_str(r#"
+feed_url: rss
+url: https://podnews.net/
+author: atareao
+email: atareao@atareao.es
+image_url: https://rustacean.net/assets/cuddlyferris.png
+category: Technology
+subcategory:
+explicit: false
+title: Podnews
+description: Prueba
+keywords: []
+license: CC BY 4.0
+"#).unwrap();
+        podcast.fill_guid();
+        // example from the podcast namespace documentation
+        assert_eq!(podcast.guid.as_deref(), Some("9b024349-ccf0-5f69-a609-6b82873eab3c"));
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use super::{Person, Transcript, Chapters, ValueBlock};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Post{
     pub slug: String,
     pub excerpt: String,
@@ -15,4 +17,24 @@ pub struct Post{
     pub length: u64,
     pub number: usize,
     pub downloads: u64,
+    pub mime_type: String,
+    pub audios: Vec<Audio>,
+    pub season: Option<u32>,
+    pub transcripts: Vec<Transcript>,
+    pub chapters: Option<Chapters>,
+    pub persons: Vec<Person>,
+    pub value: Option<ValueBlock>,
+}
+
+/// Rendition of the audio of an episode.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Audio{
+    pub filename: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// Bitrate in kbps
+    #[serde(default)]
+    pub bitrate: Option<u64>,
+    #[serde(default)]
+    pub length: u64,
 }
@@ -0,0 +1,100 @@
+use serde::{Serialize, Deserialize};
+
+/// Network where the new episodes are announced.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PublisherKind{
+    Telegram,
+    Mastodon,
+    Bluesky,
+    Matrix,
+    Discord,
+    /// POST of a JSON rendered from the template
+    Webhook,
+}
+
+impl PublisherKind{
+    pub fn get_name(&self) -> &'static str{
+        match self{
+            PublisherKind::Telegram => "telegram",
+            PublisherKind::Mastodon => "mastodon",
+            PublisherKind::Bluesky => "bluesky",
+            PublisherKind::Matrix => "matrix",
+            PublisherKind::Discord => "discord",
+            PublisherKind::Webhook => "webhook",
+        }
+    }
+}
+
+/// Publisher enabled in `config.yml`. Its credentials are read from the
+/// environment.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PublisherConfig{
+    #[serde(rename = "type")]
+    pub kind: PublisherKind,
+    /// Name in the logs and the ledger, the type if not set. Tells apart
+    /// several publishers of the same type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Template of the announcement, `<type>.html` if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Room of Matrix, as `!id:server` or `#alias:server`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    /// Url of the webhook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Environment variable with the key to sign the payload of the webhook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl PublisherConfig{
+    pub fn new(kind: PublisherKind) -> Self{
+        Self{
+            kind,
+            name: None,
+            template: None,
+            room: None,
+            url: None,
+            secret: None,
+        }
+    }
+
+    pub fn get_name(&self) -> &str{
+        self.name.as_deref().unwrap_or(self.kind.get_name())
+    }
+
+    /// Template of the announcement. The one of the webhook is a JSON.
+    pub fn get_template(&self) -> String{
+        match (&self.template, self.kind){
+            (Some(template), _) => template.clone(),
+            (None, PublisherKind::Webhook) => "webhook.json".to_string(),
+            (None, kind) => format!("{}.html", kind.get_name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PublisherConfig, PublisherKind};
+
+    #[test]
+    fn test_get_template(){
+        let publishers: Vec<PublisherConfig> = serde_yaml::from_str(r#"
+- type: telegram
+- type: mastodon
+  template: toot.html
+- type: webhook
+  name: home-assistant
+  url: http://localhost:8123/api/webhook/podcast
+"#).unwrap();
+        assert_eq!(publishers[0].kind, PublisherKind::Telegram);
+        assert_eq!(publishers[0].get_template(), "telegram.html");
+        assert_eq!(publishers[0].get_name(), "telegram");
+        assert_eq!(publishers[1].get_template(), "toot.html");
+        assert_eq!(publishers[2].get_template(), "webhook.json");
+        assert_eq!(publishers[2].get_name(), "home-assistant");
+    }
+}
@@ -0,0 +1,26 @@
+use serde::{Serialize, Deserialize};
+
+/// Rules of `robots.txt`, which always points at the sitemap.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RobotsConfig{
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub disallow: Vec<String>,
+}
+
+impl Default for RobotsConfig{
+    fn default() -> Self{
+        Self{
+            user_agent: default_user_agent(),
+            allow: Vec::new(),
+            disallow: Vec::new(),
+        }
+    }
+}
+
+fn default_user_agent() -> String {
+    "*".to_string()
+}
@@ -0,0 +1,20 @@
+use serde::{Serialize, Deserialize};
+
+/// Incremental sync with Internet Archive. Only the items published after
+/// the cursor saved in `state` are requested, and every `full_refresh_days`
+/// all of them are requested again to refresh the download counts.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncConfig{
+    #[serde(default = "default_state")]
+    pub state: String,
+    #[serde(default = "default_full_refresh_days")]
+    pub full_refresh_days: i64,
+}
+
+fn default_state() -> String {
+    "sync.yml".to_string()
+}
+
+fn default_full_refresh_days() -> i64 {
+    7
+}
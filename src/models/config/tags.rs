@@ -0,0 +1,24 @@
+use serde::{Serialize, Deserialize};
+
+/// Pages of the subjects of the episodes, in `path/<slug>/`, and whether
+/// each one has its own RSS feed in `path/<slug>/feed.xml`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagsConfig{
+    #[serde(default = "default_path")]
+    pub path: String,
+    #[serde(default)]
+    pub feed: bool,
+}
+
+impl Default for TagsConfig{
+    fn default() -> Self{
+        Self{
+            path: default_path(),
+            feed: false,
+        }
+    }
+}
+
+fn default_path() -> String {
+    "tags".to_string()
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use gray_matter::{Matter, engine::YAML};
+
+use super::{
+    error::Error,
+    utils::{get_slug, string_or_seq_string},
+};
+
+/// Front matter of an episode that is not in Internet Archive yet.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DraftMetadata{
+    pub title: String,
+    #[serde(default, deserialize_with = "string_or_seq_string")]
+    pub subject: Vec<String>,
+    pub identifier: Option<String>,
+    pub date: Option<String>,
+    pub collection: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Draft{
+    pub metadata: DraftMetadata,
+    /// Markdown used as the item description
+    pub content: String,
+}
+
+impl Draft{
+    pub async fn read(filename: &str) -> Result<Self, Error>{
+        debug!("Draft: {}", filename);
+        let data = tokio::fs::read_to_string(filename).await?;
+        let matter = Matter::<YAML>::new();
+        let result = matter.parse(&data);
+        let metadata: DraftMetadata = result.data
+            .ok_or_else(|| Error::new(&format!("{filename} has no front matter")))?
+            .deserialize()
+            .map_err(|e| Error::new(&format!("Invalid front matter in {filename}: {e}")))?;
+        Ok(Self{
+            metadata,
+            content: result.content.trim().to_string(),
+        })
+    }
+
+    /// Identifier of the item, from the front matter or from the title.
+    pub fn get_identifier(&self) -> String{
+        match &self.metadata.identifier{
+            Some(identifier) => identifier.clone(),
+            None => get_slug(&self.metadata.title),
+        }
+    }
+}
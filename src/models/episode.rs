@@ -4,11 +4,12 @@ use tracing::{debug, info, error};
 use gray_matter::{Matter, engine::YAML};
 use comrak::{markdown_to_html, ComrakOptions};
 
-use super::utils::string_or_seq_string;
+use super::utils::{string_or_seq_string, get_mime_type};
 
 use super::{
-    archive::Doc,
-    config::Post,
+    archive::{Doc, AudioFile, ItemMetadata},
+    config::{Post, Audio, MergeConfig, MergePolicy, Person, Transcript,
+        Chapters, ValueBlock},
 };
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,15 +24,31 @@ pub struct Metadata{
     //pub description: String,
     pub downloads: u64,
     // from mp3 metadata
+    #[serde(default)]
     pub filename: String,
     #[serde(default = "get_default_datetime")]
     pub datetime: Option<DateTime<Utc>>,
+    #[serde(default)]
     pub size: u64,
+    #[serde(default)]
     pub length: u64,
+    #[serde(default)]
+    pub audios: Vec<Audio>,
     pub excerpt: String,
     //pub comment: String,
     // more
     pub slug: String,
+    // Podcasting 2.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub season: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transcripts: Vec<Transcript>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chapters: Option<Chapters>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub persons: Vec<Person>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<ValueBlock>,
 }
 
 fn get_default_datetime() -> Option<DateTime<Utc>>{
@@ -43,8 +60,59 @@ fn default_number() -> usize {
 }
 
 impl Metadata{
-    pub fn get_filename(&self) -> String {
-        format!("episodes/{}.md", &self.identifier)
+    pub fn get_filename(&self, directory: &str) -> String {
+        format!("{}/{}.md", directory, &self.identifier)
+    }
+
+    /// Renditions of the audio. Episodes saved before renditions were
+    /// stored only have the main one.
+    pub fn get_audios(&self) -> Vec<Audio> {
+        if !self.audios.is_empty() || self.filename.is_empty() {
+            return self.audios.clone();
+        }
+        vec![Audio{
+            filename: self.filename.clone(),
+            mime_type: get_mime_type(&self.filename).to_string(),
+            size: self.size,
+            bitrate: None,
+            length: self.length,
+        }]
+    }
+}
+
+fn to_audios(audios: &[AudioFile]) -> Vec<Audio> {
+    audios.iter()
+        .map(|audio| Audio{
+            filename: audio.name.clone(),
+            mime_type: get_mime_type(&audio.name).to_string(),
+            size: audio.size,
+            bitrate: audio.bitrate,
+            length: audio.length,
+        })
+        .collect()
+}
+
+/// Fields changed or in conflict after merging an episode with its doc.
+#[derive(Debug, Default, PartialEq)]
+pub struct Merge{
+    pub updated: Vec<&'static str>,
+    pub conflicts: Vec<&'static str>,
+}
+
+impl Merge{
+    fn apply<T: PartialEq>(&mut self, field: &'static str, policy: MergePolicy,
+            local: &mut T, remote: T){
+        if *local == remote{
+            return;
+        }
+        match policy{
+            MergePolicy::Remote => {
+                *local = remote;
+                self.updated.push(field);
+            },
+            MergePolicy::Local => {},
+            MergePolicy::Conflict => self.conflicts.push(field),
+        }
     }
 }
 
@@ -52,11 +120,13 @@ impl Metadata{
 pub struct Episode{
     metadata: Metadata,
     pub content: String,
+    #[serde(skip)]
+    directory: String,
 }
 
 impl Episode{
     pub fn get_filename(&self) -> String {
-        self.metadata.get_filename()
+        self.metadata.get_filename(&self.directory)
     }
 
     pub fn get_identifier(&self) -> &str {
@@ -80,11 +150,20 @@ impl Episode{
             size: self.metadata.size,
             number: self.metadata.number,
             downloads: self.metadata.downloads,
+            mime_type: get_mime_type(&self.metadata.filename).to_string(),
+            audios: self.metadata.get_audios(),
+            season: self.metadata.season,
+            transcripts: self.metadata.transcripts.clone(),
+            chapters: self.metadata.chapters.clone(),
+            persons: self.metadata.persons.clone(),
+            value: self.metadata.value.clone(),
         }
     }
 
-    pub async fn new(filename: &str) -> Result<Self, serde_json::Error>{
-        let filename = format!("episodes/{}", filename);
+    /// Reads the episode `filename` from `directory` without touching the
+    /// file on disk.
+    pub async fn read(directory: &str, filename: &str) -> Result<Self, serde_json::Error>{
+        let filename = format!("{}/{}", directory, filename);
         debug!("Filename: {}", filename);
         let data = tokio::fs::read_to_string(&filename)
             .await
@@ -93,10 +172,16 @@ impl Episode{
         let result = matter.parse(&data);
         let metadata: Metadata = result.data.unwrap().deserialize()?;
         debug!("Metadata: {:?}", &metadata);
-        let episode = Self{
+        Ok(Self{
             metadata,
             content: result.content,
-        };
+            directory: directory.to_string(),
+        })
+    }
+
+    pub async fn new(directory: &str, filename: &str) -> Result<Self, serde_json::Error>{
+        let episode = Self::read(directory, filename).await?;
+        let filename = format!("{}/{}", directory, filename);
         match episode.save().await{
             Ok(_) => {
                 info!("Saved article {}", episode.get_filename());
@@ -112,6 +197,81 @@ impl Episode{
         Ok(episode)
     }
 
+    /// Returns the front matter fields that are needed to build the site
+    /// but only Internet Archive can fill in.
+    pub fn get_missing_fields(&self) -> Vec<&'static str>{
+        let mut missing = Vec::new();
+        if self.metadata.filename.is_empty(){
+            missing.push("filename");
+        }
+        if self.metadata.size == 0{
+            missing.push("size");
+        }
+        if self.metadata.length == 0{
+            missing.push("length");
+        }
+        if self.metadata.datetime.is_none(){
+            missing.push("datetime");
+        }
+        missing
+    }
+
+    /// Fills in the audio fields from a completed `Doc`.
+    pub fn complete_from(&mut self, doc: &Doc){
+        self.metadata.filename = doc.get_audio_filename().to_string();
+        self.metadata.size = doc.get_size();
+        self.metadata.length = doc.get_length();
+        self.metadata.datetime = Some(doc.get_datetime());
+        self.metadata.audios = to_audios(doc.get_audios());
+    }
+
+    /// Merges the fields that come from Internet Archive following the
+    /// policy of each one.
+    pub fn merge(&mut self, doc: &Doc, policy: &MergeConfig) -> Merge{
+        let mut merge = Merge::default();
+        merge.apply("title", policy.title, &mut self.metadata.title,
+            doc.get_title().to_string());
+        merge.apply("subject", policy.subject, &mut self.metadata.subject,
+            doc.get_subject());
+        merge.apply("excerpt", policy.excerpt, &mut self.metadata.excerpt,
+            doc.get_exceprt());
+        // the front matter parser keeps the blank lines around the content
+        if self.content.trim() != doc.get_description().trim(){
+            merge.apply("content", policy.content, &mut self.content,
+                doc.get_description().to_string());
+        }
+        merge.apply("slug", policy.slug, &mut self.metadata.slug,
+            doc.get_slug());
+        merge
+    }
+
+    /// Whether the renditions of the audio are stored in the front matter.
+    pub fn has_audios(&self) -> bool{
+        !self.metadata.audios.is_empty()
+    }
+
+    /// Fields of the front matter that are also metadata of the item. The
+    /// content is rendered to HTML, as Internet Archive stores the
+    /// description, unless it is still the description of `remote`.
+    pub fn get_item_metadata(&self, remote: &ItemMetadata) -> ItemMetadata{
+        let description = match &remote.description{
+            // the front matter parser keeps the blank lines around the content
+            Some(description) if description.trim() == self.content.trim() =>
+                description.clone(),
+            _ => {
+                let mut options = ComrakOptions::default();
+                // keep the HTML of the descriptions synced from Internet Archive
+                options.render.unsafe_ = true;
+                markdown_to_html(&self.content, &options)
+            }
+        };
+        ItemMetadata{
+            title: Some(self.metadata.title.clone()),
+            subject: self.metadata.subject.clone(),
+            description: Some(description),
+        }
+    }
+
     pub fn get_downloads(&self) -> u64{
         self.metadata.downloads
     }
@@ -131,10 +291,8 @@ impl Episode{
         debug!("Content: {}", content);
         tokio::fs::write(self.get_filename(), content).await
     }
-}
 
-impl From<Doc> for Episode{
-    fn from(doc: Doc) -> Self{
+    pub fn from_doc(doc: Doc, directory: &str) -> Self{
         let metadata = Metadata{
             number: doc.get_number(),
             identifier: doc.get_identifier().to_string(),
@@ -146,11 +304,84 @@ impl From<Doc> for Episode{
             filename: doc.get_audio_filename().to_string(),
             size: doc.get_size(),
             length: doc.get_length(),
+            audios: to_audios(doc.get_audios()),
             slug: doc.get_slug(),
+            season: None,
+            transcripts: Vec::new(),
+            chapters: None,
+            persons: Vec::new(),
+            value: None,
         };
         Self{
             metadata,
-            content: doc.get_description().to_string()
+            content: doc.get_description().to_string(),
+            directory: directory.to_string(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Episode, Merge};
+    use super::super::{
+        archive::{Doc, ItemMetadata, MetadataDiff},
+        config::{MergeConfig, MergePolicy},
+    };
+
+    #[test]
+    fn test_merge(){
+        let doc: Doc = serde_json::from_value(serde_json::json!({
+            "identifier": "prueba-2",
+            "publicdate": "2024-01-09T10:00:00Z",
+            "subject": ["rust", "neovim"],
+            "description": "Descripción nueva",
+            "title": "Prueba 2 corregido",
+            "downloads": 3,
+        })).unwrap();
+        let mut episode = Episode::from_doc(doc.clone(), "episodes");
+        episode.metadata.title = "Prueba 2".to_string();
+        episode.metadata.subject = vec!["rust".to_string()];
+        episode.content = "Notas escritas a mano".to_string();
+        let policy = MergeConfig{
+            title: MergePolicy::Remote,
+            subject: MergePolicy::Conflict,
+            content: MergePolicy::Local,
+            ..Default::default()
+        };
+        let merge = episode.merge(&doc, &policy);
+        assert_eq!(merge, Merge{
+            updated: vec!["title"],
+            conflicts: vec!["subject"],
+        });
+        assert_eq!(episode.metadata.title, "Prueba 2 corregido");
+        assert_eq!(episode.metadata.subject, vec!["rust".to_string()]);
+        assert_eq!(episode.content, "Notas escritas a mano");
+    }
+
+    #[test]
+    fn test_get_item_metadata(){
+        let doc: Doc = serde_json::from_value(serde_json::json!({
+            "identifier": "prueba-2",
+            "publicdate": "2024-01-09T10:00:00Z",
+            "subject": ["rust", "neovim"],
+            "description": "Hola<br />mundo",
+            "title": "Prueba 2",
+            "downloads": 3,
+        })).unwrap();
+        let remote = ItemMetadata{
+            title: Some("Prueba 2".to_string()),
+            subject: vec!["rust".to_string(), "neovim".to_string()],
+            description: Some("Hola<br />mundo".to_string()),
+        };
+        let mut episode = Episode::from_doc(doc, "episodes");
+        let diff = MetadataDiff::new("prueba-2", &remote, &episode.get_item_metadata(&remote));
+        assert_eq!(diff.to_patch(), serde_json::json!([]));
+        episode.content = "Notas *nuevas* con <br /> HTML".to_string();
+        let diff = MetadataDiff::new("prueba-2", &remote, &episode.get_item_metadata(&remote));
+        assert_eq!(diff.to_patch(), serde_json::json!([{
+            "op": "replace",
+            "path": "/description",
+            "value": "<p>Notas <em>nuevas</em> con <br /> HTML</p>",
+        }]));
+    }
+}
@@ -77,3 +77,9 @@ impl From<ureq::Error> for Error{
         Error::new(&error.to_string())
     }
 }
+
+impl From<serde_yaml::Error> for Error{
+    fn from(error: serde_yaml::Error) -> Self{
+        Error::new(&error.to_string())
+    }
+}
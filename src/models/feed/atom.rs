@@ -0,0 +1,107 @@
+use quick_xml::{
+    events::{BytesDecl, BytesText, Event},
+    Writer,
+};
+use std::io::{self, Cursor};
+
+use super::{Feed, Item, rss::{text, XmlWriter}};
+use super::super::error::Error;
+
+const NAMESPACE: &str = "http://www.w3.org/2005/Atom";
+
+impl Feed{
+    /// Writes the feed as Atom, with the audio as an `enclosure` link.
+    pub fn to_atom(&self, feed_url: &str) -> Result<String, Error>{
+        let podcast = &self.podcast;
+        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 4);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        writer.create_element("feed")
+            .with_attributes([
+                ("xmlns", NAMESPACE),
+                ("xml:lang", podcast.language.as_str()),
+            ])
+            .write_inner_content(|writer| {
+                text(writer, "title", &podcast.title)?;
+                text(writer, "subtitle", &podcast.description)?;
+                text(writer, "id", &podcast.url)?;
+                text(writer, "updated", &self.build_date.to_rfc3339())?;
+                link(writer, "self", feed_url)?;
+                link(writer, "alternate", &podcast.url)?;
+                writer.create_element("author")
+                    .write_inner_content(|writer| {
+                        text(writer, "name", &podcast.author)?;
+                        text(writer, "email", &podcast.email)
+                    })?;
+                text(writer, "icon", &podcast.image_url)?;
+                text(writer, "logo", &podcast.image_url)?;
+                text(writer, "rights", &podcast.license)?;
+                for item in &self.items{
+                    write_entry(writer, item)?;
+                }
+                Ok(())
+            })?;
+        let content = writer.into_inner().into_inner();
+        String::from_utf8(content).map_err(|e| Error::new(&e.to_string()))
+    }
+}
+
+fn write_entry(writer: &mut XmlWriter, item: &Item) -> io::Result<()>{
+    let post = &item.post;
+    let date = post.date.to_rfc3339();
+    writer.create_element("entry")
+        .write_inner_content(|writer| {
+            text(writer, "title", &post.title)?;
+            text(writer, "id", &item.link)?;
+            link(writer, "alternate", &item.link)?;
+            writer.create_element("link")
+                .with_attributes([
+                    ("rel", "enclosure"),
+                    ("href", item.enclosure.url.as_str()),
+                    ("type", item.enclosure.mime_type.as_str()),
+                    ("length", &item.enclosure.length.to_string()),
+                ])
+                .write_empty()?;
+            text(writer, "published", &date)?;
+            text(writer, "updated", &date)?;
+            for subject in &post.subject{
+                writer.create_element("category")
+                    .with_attribute(("term", subject.as_str()))
+                    .write_empty()?;
+            }
+            writer.create_element("summary")
+                .with_attribute(("type", "html"))
+                .write_text_content(BytesText::new(post.excerpt.trim()))?;
+            writer.create_element("content")
+                .with_attribute(("type", "html"))
+                .write_text_content(BytesText::new(post.content.trim()))?;
+            Ok(())
+        })?;
+    Ok(())
+}
+
+fn link(writer: &mut XmlWriter, rel: &str, href: &str) -> io::Result<()>{
+    writer.create_element("link")
+        .with_attributes([("rel", rel), ("href", href)])
+        .write_empty()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        Feed,
+        tests::{get_podcast, get_post},
+    };
+    use super::super::super::archive::IAClient;
+
+    #[test]
+    fn test_to_atom(){
+        let iaclient = IAClient::new("atareao", "prueba");
+        let feed = Feed::new(&get_podcast(), &iaclient, &None, &[get_post()]);
+        let atom = feed.to_atom("https://example.com/atom.xml").unwrap();
+        assert!(atom.contains(r#"<link rel="self" href="https://example.com/atom.xml"/>"#));
+        assert!(atom.contains("<title>Uno &amp; &lt;dos&gt;</title>"));
+        assert!(atom.contains(r#"<link rel="enclosure" href="https://archive.org/download/uno/uno.mp3" type="audio/mpeg" length="100"/>"#));
+        assert!(atom.contains("<published>2024-01-01T10:00:00+00:00</published>"));
+    }
+}
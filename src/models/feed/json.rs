@@ -0,0 +1,116 @@
+use serde::Serialize;
+
+use super::{Feed, Item};
+use super::super::{error::Error, striptags};
+
+const VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+#[derive(Debug, Serialize)]
+struct JsonFeed<'a>{
+    version: &'static str,
+    title: &'a str,
+    home_page_url: &'a str,
+    feed_url: &'a str,
+    description: &'a str,
+    icon: &'a str,
+    authors: Vec<Author<'a>>,
+    language: &'a str,
+    items: Vec<JsonItem<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Author<'a>{
+    name: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonItem<'a>{
+    id: &'a str,
+    url: &'a str,
+    title: &'a str,
+    content_html: &'a str,
+    summary: String,
+    date_published: String,
+    tags: &'a [String],
+    attachments: Vec<Attachment<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Attachment<'a>{
+    url: &'a str,
+    mime_type: &'a str,
+    size_in_bytes: u64,
+    duration_in_seconds: u64,
+}
+
+impl Feed{
+    /// Writes the feed as JSON Feed 1.1, with every rendition of the audio
+    /// as an attachment.
+    pub fn to_json(&self, feed_url: &str) -> Result<String, Error>{
+        let podcast = &self.podcast;
+        let feed = JsonFeed{
+            version: VERSION,
+            title: &podcast.title,
+            home_page_url: &podcast.url,
+            feed_url,
+            description: &podcast.description,
+            icon: &podcast.image_url,
+            authors: vec![Author{ name: &podcast.author }],
+            language: &podcast.language,
+            items: self.items.iter().map(to_json_item).collect(),
+        };
+        serde_json::to_string_pretty(&feed).map_err(|e| Error::new(&e.to_string()))
+    }
+}
+
+fn to_json_item(item: &Item) -> JsonItem<'_>{
+    let post = &item.post;
+    let enclosures = if item.alternate_enclosures.is_empty(){
+        std::slice::from_ref(&item.enclosure)
+    }else{
+        item.alternate_enclosures.as_slice()
+    };
+    JsonItem{
+        id: &item.guid,
+        url: &item.link,
+        title: &post.title,
+        content_html: post.content.trim(),
+        summary: striptags(post.excerpt.clone()).trim().to_string(),
+        date_published: post.date.to_rfc3339(),
+        tags: &post.subject,
+        attachments: enclosures.iter()
+            .map(|enclosure| Attachment{
+                url: &enclosure.url,
+                mime_type: &enclosure.mime_type,
+                size_in_bytes: enclosure.length,
+                duration_in_seconds: post.length,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        Feed,
+        tests::{get_podcast, get_post},
+    };
+    use super::super::super::archive::IAClient;
+
+    #[test]
+    fn test_to_json(){
+        let iaclient = IAClient::new("atareao", "prueba");
+        let feed = Feed::new(&get_podcast(), &iaclient, &None, &[get_post()]);
+        let json = feed.to_json("https://example.com/feed.json").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(json["feed_url"], "https://example.com/feed.json");
+        assert_eq!(json["items"][0]["title"], "Uno & <dos>");
+        assert_eq!(json["items"][0]["summary"], "Primero");
+        assert_eq!(json["items"][0]["date_published"], "2024-01-01T10:00:00+00:00");
+        let attachments = json["items"][0]["attachments"].as_array().unwrap();
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[1]["mime_type"], "audio/ogg");
+        assert_eq!(attachments[1]["size_in_bytes"], 80);
+    }
+}
@@ -0,0 +1,184 @@
+mod atom;
+mod json;
+mod rss;
+
+use chrono::{DateTime, Utc};
+use minijinja::Value;
+
+use super::{
+    archive::IAClient,
+    config::{Podcast, Post},
+};
+
+/// Feed of the podcast, independent of the format it is written in.
+#[derive(Debug, Clone)]
+pub struct Feed{
+    pub podcast: Podcast,
+    pub build_date: DateTime<Utc>,
+    pub items: Vec<Item>,
+    /// Extra elements for the channel, rendered from a template hook
+    pub extra: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Item{
+    pub post: Post,
+    pub link: String,
+    pub guid: String,
+    pub enclosure: Enclosure,
+    /// Every rendition of the audio, when there are more than one
+    pub alternate_enclosures: Vec<Enclosure>,
+    /// Extra elements for the item, rendered from a template hook
+    pub extra: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enclosure{
+    pub url: String,
+    pub mime_type: String,
+    pub length: u64,
+    /// Bitrate in kbps
+    pub bitrate: Option<u64>,
+    pub default: bool,
+}
+
+impl Feed{
+    /// Builds the feed from the posts, already sorted. The audios are
+    /// downloaded from `iaclient` through the `op3` prefix of `params`, if
+    /// any.
+    pub fn new(podcast: &Podcast, iaclient: &IAClient, params: &Option<Value>,
+            posts: &[Post]) -> Self{
+        let op3 = params.as_ref()
+            .and_then(|params| params.get_attr("op3").ok())
+            .and_then(|op3| op3.as_str().map(str::to_string));
+        let base_url = podcast.url.trim_end_matches('/');
+        let items = posts.iter()
+            .map(|post| {
+                let download_url = |filename: &str| {
+                    let url = iaclient.get_download_url(&post.identifier, filename);
+                    match &op3{
+                        Some(op3) => format!("https://{}/{}", op3,
+                            url.split_once("://").map(|(_, rest)| rest).unwrap_or(&url)),
+                        None => url,
+                    }
+                };
+                let alternate_enclosures = if post.audios.len() > 1{
+                    post.audios.iter()
+                        .map(|audio| Enclosure{
+                            url: download_url(&audio.filename),
+                            mime_type: audio.mime_type.clone(),
+                            length: audio.size,
+                            bitrate: audio.bitrate,
+                            default: audio.filename == post.filename,
+                        })
+                        .collect()
+                }else{
+                    Vec::new()
+                };
+                Item{
+                    link: format!("{}/{}/", base_url, post.slug),
+                    guid: format!("/{}/", post.slug),
+                    enclosure: Enclosure{
+                        url: download_url(&post.filename),
+                        mime_type: post.mime_type.clone(),
+                        length: post.size,
+                        bitrate: None,
+                        default: true,
+                    },
+                    alternate_enclosures,
+                    post: post.clone(),
+                    extra: None,
+                }
+            })
+            .collect();
+        Self{
+            podcast: podcast.clone(),
+            build_date: Utc::now(),
+            items,
+            extra: None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::{TimeZone, Utc};
+    use minijinja::Value;
+    use super::Feed;
+    use super::super::{
+        archive::IAClient,
+        config::{Audio, Podcast, Post},
+    };
+
+    pub fn get_podcast() -> Podcast{
+        serde_yaml::from_str(r#"
+feed_url: feed.xml
+url: https://example.com/
+author: atareao
+email: atareao@atareao.es
+image_url: https://example.com/cover.png
+category: Technology
+subcategory:
+explicit: false
+title: Rust & <Linux>
+description: Prueba
+keywords: [rust]
+license: CC BY 4.0
+guid: 9b024349-ccf0-5f69-a609-6b82873eab3c
+"#).unwrap()
+    }
+
+    pub fn get_post() -> Post{
+        Post{
+            slug: "uno".to_string(),
+            excerpt: "<p>Primero</p>".to_string(),
+            title: "Uno & <dos>".to_string(),
+            content: "<p>Contenido ]]> raro</p>".to_string(),
+            subject: vec!["rust".to_string()],
+            date: Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap(),
+            identifier: "uno".to_string(),
+            filename: "uno.mp3".to_string(),
+            size: 100,
+            length: 3723,
+            number: 1,
+            downloads: 0,
+            mime_type: "audio/mpeg".to_string(),
+            audios: vec![
+                Audio{
+                    filename: "uno.mp3".to_string(),
+                    mime_type: "audio/mpeg".to_string(),
+                    size: 100,
+                    bitrate: Some(128),
+                    length: 3723,
+                },
+                Audio{
+                    filename: "uno.ogg".to_string(),
+                    mime_type: "audio/ogg".to_string(),
+                    size: 80,
+                    bitrate: None,
+                    length: 3723,
+                },
+            ],
+            season: None,
+            transcripts: Vec::new(),
+            chapters: None,
+            persons: Vec::new(),
+            value: None,
+        }
+    }
+
+    #[test]
+    fn test_new(){
+        let params = Some(Value::from_serialize(
+            serde_json::json!({"op3": "op3.dev/e,pg=abc"})));
+        let iaclient = IAClient::new("atareao", "prueba");
+        let feed = Feed::new(&get_podcast(), &iaclient, &params, &[get_post()]);
+        let item = &feed.items[0];
+        assert_eq!(item.link, "https://example.com/uno/");
+        assert_eq!(item.guid, "/uno/");
+        assert_eq!(item.enclosure.url,
+            "https://op3.dev/e,pg=abc/archive.org/download/uno/uno.mp3");
+        assert_eq!(item.alternate_enclosures.len(), 2);
+        assert!(!item.alternate_enclosures[1].default);
+    }
+}
@@ -0,0 +1,364 @@
+use quick_xml::{
+    events::{BytesDecl, BytesText, Event},
+    Reader,
+    Writer,
+};
+use std::io::{self, Cursor};
+
+use super::{Feed, Item, Enclosure};
+use super::super::{
+    config::{Person, ValueBlock},
+    error::Error,
+    utils::from_sec,
+};
+
+const NAMESPACES: [(&str, &str); 6] = [
+    ("xmlns:content", "http://purl.org/rss/1.0/modules/content/"),
+    ("xmlns:dc", "http://purl.org/dc/elements/1.1/"),
+    ("xmlns:atom", "http://www.w3.org/2005/Atom"),
+    ("xmlns:sy", "http://purl.org/rss/1.0/modules/syndication/"),
+    ("xmlns:itunes", "http://www.itunes.com/dtds/podcast-1.0.dtd"),
+    ("xmlns:podcast", "https://podcastindex.org/namespace/1.0"),
+];
+const GENERATOR: &str = "https://atareao.es";
+
+pub(super) type XmlWriter = Writer<Cursor<Vec<u8>>>;
+
+impl Feed{
+    /// Writes the feed as RSS 2.0 with the iTunes and Podcasting 2.0
+    /// namespaces.
+    pub fn to_rss(&self, feed_url: &str) -> Result<String, Error>{
+        let channel_extra = parse_extra(self.extra.as_deref())
+            .map_err(|e| Error::new(&format!("Invalid extra elements for the channel. {e}")))?;
+        let mut items_extra = Vec::new();
+        for item in &self.items{
+            items_extra.push(parse_extra(item.extra.as_deref())
+                .map_err(|e| Error::new(&format!("Invalid extra elements for episode {}. {e}",
+                    item.post.identifier)))?);
+        }
+        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 4);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        writer.create_element("rss")
+            .with_attribute(("version", "2.0"))
+            .with_attributes(NAMESPACES)
+            .write_inner_content(|writer| {
+                writer.create_element("channel")
+                    .write_inner_content(|writer| {
+                        self.write_channel(writer, feed_url, &channel_extra)?;
+                        for (item, extra) in self.items.iter().zip(&items_extra){
+                            write_item(writer, self, item, extra)?;
+                        }
+                        Ok(())
+                    })?;
+                Ok(())
+            })?;
+        let content = writer.into_inner().into_inner();
+        String::from_utf8(content).map_err(|e| Error::new(&e.to_string()))
+    }
+
+    fn write_channel(&self, writer: &mut XmlWriter, feed_url: &str, extra: &[Event<'static>])
+            -> io::Result<()>{
+        let podcast = &self.podcast;
+        let build_date = self.build_date.to_rfc2822();
+        let owner = format!("{} ({})", podcast.email, podcast.author);
+        text(writer, "title", &podcast.title)?;
+        writer.create_element("atom:link")
+            .with_attributes([
+                ("href", feed_url),
+                ("rel", "self"),
+                ("type", "application/rss+xml"),
+            ])
+            .write_empty()?;
+        text(writer, "link", &podcast.url)?;
+        text(writer, "description", &podcast.description)?;
+        text(writer, "lastBuildDate", &build_date)?;
+        text(writer, "language", &podcast.language)?;
+        text(writer, "sy:updatePeriod", "weekly")?;
+        text(writer, "sy:updateFrequency", "1")?;
+        text(writer, "generator", GENERATOR)?;
+        text(writer, "itunes:summary", &podcast.description)?;
+        text(writer, "itunes:author", &podcast.author)?;
+        text(writer, "itunes:explicit", &podcast.explicit.to_string())?;
+        writer.create_element("itunes:image")
+            .with_attribute(("href", podcast.image_url.as_str()))
+            .write_empty()?;
+        writer.create_element("itunes:owner")
+            .write_inner_content(|writer| {
+                text(writer, "itunes:name", &podcast.author)?;
+                text(writer, "itunes:email", &podcast.email)
+            })?;
+        text(writer, "managingEditor", &owner)?;
+        text(writer, "itunes:subtitle", &podcast.description)?;
+        writer.create_element("image")
+            .write_inner_content(|writer| {
+                text(writer, "title", &podcast.title)?;
+                text(writer, "url", &podcast.image_url)?;
+                text(writer, "link", &podcast.url)
+            })?;
+        let category = writer.create_element("itunes:category")
+            .with_attribute(("text", podcast.category.as_str()));
+        match &podcast.subcategory{
+            Some(subcategory) => category.write_inner_content(|writer| {
+                writer.create_element("itunes:category")
+                    .with_attribute(("text", subcategory.as_str()))
+                    .write_empty()?;
+                Ok(())
+            })?,
+            None => category.write_empty()?,
+        };
+        if let Some(guid) = &podcast.guid{
+            text(writer, "podcast:guid", guid)?;
+        }
+        writer.create_element("podcast:locked")
+            .with_attribute(("owner", podcast.email.as_str()))
+            .write_text_content(BytesText::new(if podcast.locked {"yes"} else {"no"}))?;
+        let mut license = writer.create_element("podcast:license");
+        if let Some(url) = &podcast.license_url{
+            license = license.with_attribute(("url", url.as_str()));
+        }
+        license.write_text_content(BytesText::new(&podcast.license))?;
+        for funding in &podcast.funding{
+            writer.create_element("podcast:funding")
+                .with_attribute(("url", funding.url.as_str()))
+                .write_text_content(BytesText::new(&funding.text))?;
+        }
+        for person in &podcast.persons{
+            write_person(writer, person)?;
+        }
+        if let Some(value) = &podcast.value{
+            write_value(writer, value)?;
+        }
+        write_extra(writer, extra)
+    }
+}
+
+fn write_item(writer: &mut XmlWriter, feed: &Feed, item: &Item, extra: &[Event<'static>])
+        -> io::Result<()>{
+    let podcast = &feed.podcast;
+    let post = &item.post;
+    writer.create_element("item")
+        .write_inner_content(|writer| {
+            text(writer, "title", &post.title)?;
+            text(writer, "link", &item.link)?;
+            text(writer, "comments", &format!("{}#comments", item.link))?;
+            text(writer, "pubDate", &post.date.to_rfc2822())?;
+            for category in &post.subject{
+                text(writer, "category", category)?;
+            }
+            text(writer, "category", &podcast.category)?;
+            writer.create_element("guid")
+                .with_attribute(("isPermaLink", "false"))
+                .write_text_content(BytesText::new(&item.guid))?;
+            text(writer, "description", post.excerpt.trim())?;
+            text(writer, "content:encoded", post.content.trim())?;
+            write_enclosure(writer, &item.enclosure)?;
+            for enclosure in &item.alternate_enclosures{
+                write_alternate_enclosure(writer, enclosure)?;
+            }
+            text(writer, "itunes:subtitle", post.excerpt.trim())?;
+            text(writer, "itunes:summary", post.content.trim())?;
+            text(writer, "itunes:author", &podcast.author)?;
+            writer.create_element("itunes:image")
+                .with_attribute(("href", podcast.image_url.as_str()))
+                .write_empty()?;
+            text(writer, "itunes:explicit", &podcast.explicit.to_string())?;
+            text(writer, "itunes:duration", &from_sec(post.length))?;
+            text(writer, "dc:creator", &format!("{} ({})", podcast.email, podcast.author))?;
+            text(writer, "itunes:keywords", &post.subject.join(","))?;
+            if let Some(season) = post.season{
+                text(writer, "podcast:season", &season.to_string())?;
+            }
+            if post.number > 0{
+                text(writer, "podcast:episode", &post.number.to_string())?;
+            }
+            for transcript in &post.transcripts{
+                let mut element = writer.create_element("podcast:transcript")
+                    .with_attributes([
+                        ("url", transcript.url.as_str()),
+                        ("type", transcript.mime_type.as_str()),
+                    ]);
+                if let Some(language) = &transcript.language{
+                    element = element.with_attribute(("language", language.as_str()));
+                }
+                if let Some(rel) = &transcript.rel{
+                    element = element.with_attribute(("rel", rel.as_str()));
+                }
+                element.write_empty()?;
+            }
+            if let Some(chapters) = &post.chapters{
+                writer.create_element("podcast:chapters")
+                    .with_attributes([
+                        ("url", chapters.url.as_str()),
+                        ("type", chapters.mime_type.as_str()),
+                    ])
+                    .write_empty()?;
+            }
+            for person in &post.persons{
+                write_person(writer, person)?;
+            }
+            if let Some(value) = &post.value{
+                write_value(writer, value)?;
+            }
+            write_extra(writer, extra)
+        })?;
+    Ok(())
+}
+
+fn write_enclosure(writer: &mut XmlWriter, enclosure: &Enclosure) -> io::Result<()>{
+    writer.create_element("enclosure")
+        .with_attributes([
+            ("url", enclosure.url.as_str()),
+            ("length", &enclosure.length.to_string()),
+            ("type", enclosure.mime_type.as_str()),
+        ])
+        .write_empty()?;
+    Ok(())
+}
+
+fn write_alternate_enclosure(writer: &mut XmlWriter, enclosure: &Enclosure) -> io::Result<()>{
+    let length = enclosure.length.to_string();
+    let bitrate = enclosure.bitrate.map(|bitrate| (bitrate * 1000).to_string());
+    let mut element = writer.create_element("podcast:alternateEnclosure")
+        .with_attributes([
+            ("type", enclosure.mime_type.as_str()),
+            ("length", &length),
+        ]);
+    if let Some(bitrate) = &bitrate{
+        element = element.with_attribute(("bitrate", bitrate.as_str()));
+    }
+    if enclosure.default{
+        element = element.with_attribute(("default", "true"));
+    }
+    element.write_inner_content(|writer| {
+        writer.create_element("podcast:source")
+            .with_attribute(("uri", enclosure.url.as_str()))
+            .write_empty()?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+fn write_person(writer: &mut XmlWriter, person: &Person) -> io::Result<()>{
+    let mut element = writer.create_element("podcast:person");
+    for (name, value) in [
+        ("role", &person.role),
+        ("group", &person.group),
+        ("img", &person.img),
+        ("href", &person.href),
+    ]{
+        if let Some(value) = value{
+            element = element.with_attribute((name, value.as_str()));
+        }
+    }
+    element.write_text_content(BytesText::new(&person.name))?;
+    Ok(())
+}
+
+fn write_value(writer: &mut XmlWriter, value: &ValueBlock) -> io::Result<()>{
+    let mut element = writer.create_element("podcast:value")
+        .with_attributes([
+            ("type", value.kind.as_str()),
+            ("method", value.method.as_str()),
+        ]);
+    if let Some(suggested) = &value.suggested{
+        element = element.with_attribute(("suggested", suggested.as_str()));
+    }
+    element.write_inner_content(|writer| {
+        for recipient in &value.recipients{
+            let split = recipient.split.to_string();
+            let mut element = writer.create_element("podcast:valueRecipient")
+                .with_attributes([
+                    ("name", recipient.name.as_str()),
+                    ("type", recipient.kind.as_str()),
+                    ("address", recipient.address.as_str()),
+                    ("split", &split),
+                ]);
+            if let Some(custom_key) = &recipient.custom_key{
+                element = element.with_attribute(("customKey", custom_key.as_str()));
+            }
+            if let Some(custom_value) = &recipient.custom_value{
+                element = element.with_attribute(("customValue", custom_value.as_str()));
+            }
+            if recipient.fee{
+                element = element.with_attribute(("fee", "true"));
+            }
+            element.write_empty()?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+pub(super) fn text(writer: &mut XmlWriter, name: &str, content: &str) -> io::Result<()>{
+    writer.create_element(name).write_text_content(BytesText::new(content))?;
+    Ok(())
+}
+
+fn write_extra(writer: &mut XmlWriter, extra: &[Event<'static>]) -> io::Result<()>{
+    for event in extra{
+        writer.write_event(event.borrow())?;
+    }
+    Ok(())
+}
+
+/// Parses the extra elements rendered by a template hook, so that a broken
+/// one is reported instead of producing an invalid feed.
+fn parse_extra(extra: Option<&str>) -> Result<Vec<Event<'static>>, Error>{
+    let mut events = Vec::new();
+    let extra = match extra.map(str::trim){
+        Some(extra) if !extra.is_empty() => format!("<extra>{extra}</extra>"),
+        _ => return Ok(events),
+    };
+    let mut reader = Reader::from_str(&extra);
+    let mut depth = 0;
+    let mut closed = false;
+    loop{
+        let event = reader.read_event().map_err(|e| Error::new(&e.to_string()))?;
+        match &event{
+            Event::Eof => break,
+            Event::Text(text) if text.xml10_content().trim().is_empty() => continue,
+            _ if closed => return Err(Error::new("Unbalanced XML")),
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {},
+        }
+        closed = depth == 0;
+        events.push(event.into_owned());
+    }
+    if !closed{
+        return Err(Error::new("Unbalanced XML"));
+    }
+    // the <extra> wrapper
+    events.pop();
+    events.remove(0);
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        Feed,
+        tests::{get_podcast, get_post},
+    };
+    use super::super::super::{archive::IAClient, validator::check_feed};
+
+    #[test]
+    fn test_to_rss(){
+        let iaclient = IAClient::new("atareao", "prueba");
+        let posts = vec![get_post()];
+        let mut feed = Feed::new(&get_podcast(), &iaclient, &None, &posts);
+        feed.items[0].extra = Some(r#"<podcast:soundbite startTime="10" duration="5">A &amp; B</podcast:soundbite>"#.to_string());
+        let rss = feed.to_rss("https://example.com/feed.xml").unwrap();
+        assert!(rss.contains("<title>Rust &amp; &lt;Linux&gt;</title>"));
+        assert!(rss.contains("<title>Uno &amp; &lt;dos&gt;</title>"));
+        assert!(rss.contains("<itunes:duration>1:02:03</itunes:duration>"));
+        assert!(rss.contains("<podcast:soundbite startTime=\"10\" duration=\"5\">A &amp; B</podcast:soundbite>"));
+        let violations = check_feed(&rss, &posts).unwrap();
+        assert!(violations.is_empty(), "{:?}", violations);
+
+        feed.items[0].extra = Some("<podcast:soundbite>".to_string());
+        assert!(feed.to_rss("https://example.com/feed.xml").is_err());
+        feed.items[0].extra = Some("</item><item>".to_string());
+        assert!(feed.to_rss("https://example.com/feed.xml").is_err());
+    }
+}
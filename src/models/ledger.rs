@@ -0,0 +1,191 @@
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Utc};
+use tracing::debug;
+use std::{collections::BTreeMap, io::ErrorKind};
+
+use super::{error::Error, publisher::Receipt};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status{
+    /// Waiting to be sent, usually because the publisher had no credentials
+    Pending,
+    Sent,
+    Failed,
+}
+
+/// Last announcement of an episode with a publisher.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Entry{
+    pub status: Status,
+    /// When the status changed
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// Announcements of every episode by identifier and publisher, so that an
+/// episode is never announced twice and the failed ones can be retried.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Ledger{
+    #[serde(default)]
+    pub episodes: BTreeMap<String, BTreeMap<String, Entry>>,
+}
+
+impl Ledger{
+    /// Reads the ledger from `path`, or an empty one if it does not exist.
+    /// A ledger that can not be read is an error, as starting from an empty
+    /// one would announce again every episode.
+    pub async fn read(path: &str) -> Result<Self, Error>{
+        match tokio::fs::read_to_string(path).await{
+            Ok(content) => serde_yaml::from_str(&content)
+                .map_err(|e| Error::new(&format!("Can not parse ledger {path}: {e}"))),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                debug!("Can not read ledger {path}: {e}");
+                Ok(Self::default())
+            }
+            Err(e) => Err(Error::new(&format!("Can not read ledger {path}: {e}"))),
+        }
+    }
+
+    pub async fn save(&self, path: &str) -> tokio::io::Result<()>{
+        tokio::fs::write(path, serde_yaml::to_string(self).unwrap()).await
+    }
+
+    pub fn get(&self, identifier: &str, publisher: &str) -> Option<&Entry>{
+        self.episodes.get(identifier)?.get(publisher)
+    }
+
+    pub fn is_sent(&self, identifier: &str, publisher: &str) -> bool{
+        self.get(identifier, publisher)
+            .is_some_and(|entry| entry.status == Status::Sent)
+    }
+
+    /// Records that `identifier` has to be announced with `publisher`,
+    /// unless there is already an entry.
+    pub fn add_pending(&mut self, identifier: &str, publisher: &str, now: DateTime<Utc>){
+        self.episodes.entry(identifier.to_string())
+            .or_default()
+            .entry(publisher.to_string())
+            .or_insert(Entry{
+                status: Status::Pending,
+                timestamp: now,
+                remote_id: None,
+                remote_url: None,
+                last_error: None,
+            });
+    }
+
+    pub fn add_sent(&mut self, identifier: &str, publisher: &str, receipt: Receipt,
+            now: DateTime<Utc>){
+        self.set(identifier, publisher, Entry{
+            status: Status::Sent,
+            timestamp: now,
+            remote_id: receipt.id,
+            remote_url: receipt.url,
+            last_error: None,
+        });
+    }
+
+    pub fn add_failure(&mut self, identifier: &str, publisher: &str, error: &str,
+            now: DateTime<Utc>){
+        self.set(identifier, publisher, Entry{
+            status: Status::Failed,
+            timestamp: now,
+            remote_id: None,
+            remote_url: None,
+            last_error: Some(error.to_string()),
+        });
+    }
+
+    /// Identifiers of the episodes with an announcement pending or failed
+    /// in any of `publishers`. A publisher without an entry is not pending,
+    /// so that enabling a new one does not announce every episode again.
+    pub fn get_pending(&self, publishers: &[&str]) -> Vec<String>{
+        self.episodes.iter()
+            .filter(|(_, entries)| entries.iter().any(|(publisher, entry)|
+                entry.status != Status::Sent && publishers.contains(&publisher.as_str())))
+            .map(|(identifier, _)| identifier.clone())
+            .collect()
+    }
+
+    fn set(&mut self, identifier: &str, publisher: &str, entry: Entry){
+        self.episodes.entry(identifier.to_string())
+            .or_default()
+            .insert(publisher.to_string(), entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use super::{Ledger, Status};
+    use super::super::publisher::Receipt;
+
+    #[test]
+    fn test_ledger(){
+        let now = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let mut ledger = Ledger::default();
+        for publisher in ["telegram", "mastodon"]{
+            ledger.add_pending("uno", publisher, now);
+            ledger.add_pending("dos", publisher, now);
+        }
+        ledger.add_sent("uno", "telegram", Receipt{
+            id: Some("1".to_string()),
+            url: Some("https://t.me/canal/1".to_string()),
+        }, now);
+        ledger.add_sent("uno", "mastodon", Receipt::default(), now);
+        ledger.add_failure("dos", "telegram", "timeout", now);
+        // A pending entry does not overwrite the last announcement
+        ledger.add_pending("uno", "telegram", now);
+        assert!(ledger.is_sent("uno", "telegram"));
+        assert!(!ledger.is_sent("dos", "telegram"));
+        let entry = ledger.get("dos", "telegram").unwrap();
+        assert_eq!(entry.status, Status::Failed);
+        assert_eq!(entry.last_error.as_deref(), Some("timeout"));
+        assert_eq!(ledger.get_pending(&["telegram", "mastodon"]), vec!["dos".to_string()]);
+        assert!(ledger.get_pending(&[]).is_empty());
+        let yaml = serde_yaml::to_string(&ledger).unwrap();
+        let ledger: Ledger = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(ledger.get("uno", "telegram").unwrap().remote_url.as_deref(),
+            Some("https://t.me/canal/1"));
+    }
+
+    #[test]
+    fn test_get_pending(){
+        let now = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let mut ledger = Ledger::default();
+        // Saved by a sync that did not announce it
+        for publisher in ["telegram", "mastodon"]{
+            ledger.add_pending("uno", publisher, now);
+        }
+        assert_eq!(ledger.get_pending(&["telegram", "mastodon"]), vec!["uno".to_string()]);
+        ledger.add_sent("uno", "telegram", Receipt::default(), now);
+        ledger.add_sent("uno", "mastodon", Receipt::default(), now);
+        assert!(ledger.get_pending(&["telegram", "mastodon"]).is_empty());
+        // A publisher added to an existing ledger does not announce the
+        // episodes already recorded, only the ones synced from then on
+        assert!(ledger.get_pending(&["telegram", "mastodon", "bluesky"]).is_empty());
+        for publisher in ["telegram", "mastodon", "bluesky"]{
+            ledger.add_pending("dos", publisher, now);
+        }
+        assert_eq!(ledger.get_pending(&["telegram", "mastodon", "bluesky"]),
+            vec!["dos".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_read(){
+        let directory = std::env::temp_dir().join(format!("ledger-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("announcements.yml");
+        let path = path.to_str().unwrap();
+        assert!(Ledger::read(path).await.unwrap().episodes.is_empty());
+        std::fs::write(path, "episodes: [").unwrap();
+        assert!(Ledger::read(path).await.is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use std::collections::BTreeMap;
+
+use super::error::Error;
+
+/// Content hashes of the inputs and outputs of a build, so that the next
+/// one only writes the files that changed and deletes the ones that are not
+/// generated anymore. The outputs are relative to the public directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest{
+    #[serde(default)]
+    inputs: BTreeMap<String, String>,
+    #[serde(default)]
+    outputs: BTreeMap<String, String>,
+    /// Outputs of the previous build
+    #[serde(skip)]
+    previous: BTreeMap<String, String>,
+    /// Record the outputs without writing them
+    #[serde(skip)]
+    dry_run: bool,
+}
+
+impl Manifest{
+    /// Reads the manifest of the last build from `path`. None if there is
+    /// none or it can not be read.
+    pub async fn read(path: &str) -> Option<Self>{
+        match tokio::fs::read_to_string(path).await{
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| debug!("Can not parse {}. {}", path, e))
+                .ok(),
+            Err(e) => {
+                debug!("Can not read {}. {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Manifest of a build whose outputs are `files`, the ones already in
+    /// the public directory when there is no manifest. As their hashes are
+    /// unknown they are written again, and the ones not generated are
+    /// deleted.
+    pub fn from_files(files: Vec<String>) -> Self{
+        Self{
+            outputs: files.into_iter()
+                .map(|file| (file, String::new()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Starts a new build with `inputs`, comparing its outputs with the ones
+    /// of this build.
+    pub fn next(self, inputs: BTreeMap<String, String>, dry_run: bool) -> Self{
+        Self{
+            inputs,
+            outputs: BTreeMap::new(),
+            previous: self.outputs,
+            dry_run,
+        }
+    }
+
+    pub fn get_inputs(&self) -> &BTreeMap<String, String>{
+        &self.inputs
+    }
+
+    pub fn is_dry_run(&self) -> bool{
+        self.dry_run
+    }
+
+    /// Whether every output of this build is still in `public`.
+    pub async fn is_complete(&self, public: &str) -> bool{
+        for path in self.outputs.keys(){
+            if tokio::fs::metadata(format!("{}/{}", public, path)).await.is_err(){
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Records `path` as generated with `content`. Returns false if it is
+    /// the same as in the previous build.
+    pub fn add_output(&mut self, path: &str, content: &[u8]) -> bool{
+        let hash = hash(content);
+        let changed = self.previous.get(path) != Some(&hash);
+        self.outputs.insert(path.to_string(), hash);
+        changed
+    }
+
+    /// Outputs of the previous build that have not been generated in this
+    /// one.
+    pub fn get_orphans(&self) -> Vec<String>{
+        self.previous.keys()
+            .filter(|path| !self.outputs.contains_key(*path))
+            .cloned()
+            .collect()
+    }
+
+    /// Forgets the inputs, so that the next build generates everything
+    /// again.
+    pub fn clear_inputs(&mut self){
+        self.inputs.clear();
+    }
+
+    pub async fn save(&self, path: &str) -> Result<(), Error>{
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::new(&e.to_string()))?;
+        tokio::fs::write(path, content).await?;
+        Ok(())
+    }
+}
+
+/// SHA-256 of `content` in hexadecimal.
+pub fn hash(content: &[u8]) -> String{
+    format!("{:x}", Sha256::digest(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use super::{hash, Manifest};
+
+    #[test]
+    fn test_next(){
+        let mut manifest = Manifest::default();
+        assert!(manifest.add_output("uno/index.html", b"uno"));
+        assert!(manifest.add_output("dos/index.html", b"dos"));
+        let inputs = BTreeMap::from([("config".to_string(), hash(b"config"))]);
+        let mut manifest = manifest.next(inputs.clone(), false);
+        assert_eq!(manifest.get_inputs(), &inputs);
+        assert!(!manifest.add_output("uno/index.html", b"uno"));
+        assert!(manifest.add_output("tres/index.html", b"tres"));
+        assert_eq!(manifest.get_orphans(), vec!["dos/index.html".to_string()]);
+    }
+
+    #[test]
+    fn test_from_files(){
+        let manifest = Manifest::from_files(vec!["uno/index.html".to_string(),
+            "viejo/index.html".to_string()]);
+        let mut manifest = manifest.next(BTreeMap::new(), false);
+        assert!(manifest.add_output("uno/index.html", b"uno"));
+        assert_eq!(manifest.get_orphans(), vec!["viejo/index.html".to_string()]);
+    }
+
+    #[test]
+    fn test_hash(){
+        assert_eq!(hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
@@ -1,29 +1,57 @@
 pub mod archive;
 pub mod publisher;
 pub mod config;
+pub mod draft;
 pub mod error;
 pub mod episode;
+pub mod feed;
+pub mod ledger;
+pub mod manifest;
+pub mod paginator;
+pub mod plan;
+pub mod search;
+pub mod sitemap;
+pub mod tag;
+pub mod validator;
 mod utils;
 
 
 use minijinja::{Environment, path_loader};
 use once_cell::sync::Lazy;
+use std::sync::RwLock;
 use chrono::{DateTime, FixedOffset};
 use chrono_tz::Tz;
 use minijinja::value::{Kwargs, Value};
 use minijinja::{Error as MiniError, ErrorKind, State};
 
-pub static ENV: Lazy<Environment<'static>> = Lazy::new(|| {
+/// Templates environment. It reads the templates from `templates` until
+/// `set_templates` points it somewhere else.
+pub static ENV: Lazy<RwLock<Environment<'static>>> = Lazy::new(|| {
     let mut env = Environment::new();
     env.set_loader(path_loader("templates"));
     env.add_filter("striptags", striptags);
     env.add_filter("date", date);
     env.add_filter("truncate", truncate);
+    env.add_filter("duration", duration);
+    env.add_filter("slug", slug);
     env.add_function("now", now);
     env.add_function("endswith", endswith);
-    env
+    RwLock::new(env)
 });
 
+/// Reads the templates from `templates`, forgetting the ones already loaded
+/// so that they are read again on the next render.
+pub fn set_templates(templates: &str) {
+    let mut env = ENV.write().unwrap();
+    env.set_loader(path_loader(templates));
+    env.clear_templates();
+}
+
+/// Renders the template `name` with `ctx`.
+pub fn render(name: &str, ctx: Value) -> Result<String, MiniError> {
+    ENV.read().unwrap().get_template(name)?.render(ctx)
+}
+
 fn endswith(value: String, endpart: String) -> bool {
     value.ends_with(&endpart)
 }
@@ -98,6 +126,15 @@ pub fn truncate(_state: &State, value: Value, kwargs: Kwargs) -> Result<String,
     }
 }
 
+/// Duration in seconds as `[hh:]mm:ss`, the way `itunes:duration` expects it.
+fn duration(seconds: u64) -> String {
+    utils::from_sec(seconds)
+}
+
+fn slug(value: String) -> String {
+    utils::get_slug(&value)
+}
+
 pub fn now() -> String {
     chrono::Utc::now().to_rfc3339()
 }
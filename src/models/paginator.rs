@@ -0,0 +1,111 @@
+use serde::Serialize;
+use chrono::Datelike;
+
+use super::config::Post;
+
+/// Page of the index. `prev` and `next` are the paths of the neighbour
+/// pages, relative to the site, the first page being the empty path.
+#[derive(Debug, Serialize, Clone)]
+pub struct Pager{
+    pub number: usize,
+    pub total: usize,
+    pub path: String,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    pub posts: Vec<Post>,
+}
+
+/// Episodes of a year.
+#[derive(Debug, Serialize, Clone)]
+pub struct Year{
+    pub year: i32,
+    pub count: usize,
+    pub posts: Vec<Post>,
+}
+
+/// Splits `posts` in pages of `per_page`, the first one in the root and the
+/// others in `path/<number>/`. A single page if `per_page` is 0.
+pub fn paginate(posts: &[Post], per_page: usize, path: &str) -> Vec<Pager>{
+    let chunks: Vec<&[Post]> = if per_page == 0 || posts.is_empty(){
+        vec![posts]
+    }else{
+        posts.chunks(per_page).collect()
+    };
+    let total = chunks.len();
+    let get_path = |number: usize| if number == 1{
+        String::new()
+    }else{
+        format!("{}/{}", path, number)
+    };
+    chunks.into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let number = i + 1;
+            Pager{
+                number,
+                total,
+                path: get_path(number),
+                prev: (number > 1).then(|| get_path(number - 1)),
+                next: (number < total).then(|| get_path(number + 1)),
+                posts: chunk.to_vec(),
+            }
+        })
+        .collect()
+}
+
+/// Groups `posts` by the year of their date, newest year first, keeping
+/// the order of the posts.
+pub fn group_by_year(posts: &[Post]) -> Vec<Year>{
+    let mut years: Vec<Year> = Vec::new();
+    for post in posts{
+        let year = post.date.year();
+        match years.iter_mut().find(|other| other.year == year){
+            Some(other) => {
+                other.posts.push(post.clone());
+                other.count += 1;
+            },
+            None => years.push(Year{
+                year,
+                count: 1,
+                posts: vec![post.clone()],
+            }),
+        }
+    }
+    years.sort_by_key(|year| std::cmp::Reverse(year.year));
+    years
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{paginate, group_by_year};
+    use super::super::config::Post;
+
+    #[test]
+    fn test_paginate(){
+        let posts: Vec<Post> = [
+            ("tres", "2024-02-01T10:00:00Z"),
+            ("dos", "2023-12-01T10:00:00Z"),
+            ("uno", "2023-01-01T10:00:00Z"),
+        ].into_iter()
+            .map(|(identifier, date)| Post{
+                identifier: identifier.to_string(),
+                date: date.parse().unwrap(),
+                ..Default::default()
+            })
+            .collect();
+        let pagers = paginate(&posts, 2, "page");
+        assert_eq!(pagers.len(), 2);
+        assert_eq!(pagers[0].path, "");
+        assert_eq!(pagers[0].prev, None);
+        assert_eq!(pagers[0].next.as_deref(), Some("page/2"));
+        assert_eq!(pagers[1].path, "page/2");
+        assert_eq!(pagers[1].prev.as_deref(), Some(""));
+        assert_eq!(pagers[1].posts[0].identifier, "uno");
+        assert_eq!(paginate(&posts, 0, "page")[0].posts.len(), 3);
+
+        let years = group_by_year(&posts);
+        assert_eq!(years.len(), 2);
+        assert_eq!(years[0].year, 2024);
+        assert_eq!(years[1].count, 2);
+    }
+}
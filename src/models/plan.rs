@@ -0,0 +1,191 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::{self, Display};
+
+/// Actions that a dry run would have carried out.
+#[derive(Debug, Default, Serialize)]
+pub struct Plan{
+    pub new_episodes: Vec<NewEpisode>,
+    pub downloads: Vec<DownloadsChange>,
+    pub merges: Vec<FieldChange>,
+    pub conflicts: Vec<FieldChange>,
+    pub deletions: Vec<String>,
+    pub announcements: Vec<Announcement>,
+    /// Announcements left pending as their publishers have no credentials
+    pub skipped: Vec<SkippedAnnouncement>,
+    pub pushes: Vec<MetadataPush>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewEpisode{
+    pub identifier: String,
+    pub title: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DownloadsChange{
+    pub identifier: String,
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange{
+    pub identifier: String,
+    pub field: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Announcement{
+    pub publisher: String,
+    pub identifier: String,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedAnnouncement{
+    pub publisher: String,
+    pub identifier: String,
+}
+
+/// Field of an episode that would be written to Internet Archive.
+#[derive(Debug, Serialize)]
+pub struct MetadataPush{
+    pub identifier: String,
+    pub field: String,
+    pub remote: Option<Value>,
+    pub local: Value,
+}
+
+impl Plan{
+    pub fn add_new_episode(&mut self, identifier: &str, title: &str){
+        self.new_episodes.push(NewEpisode{
+            identifier: identifier.to_string(),
+            title: title.to_string(),
+        });
+    }
+
+    pub fn add_downloads(&mut self, identifier: &str, from: u64, to: u64){
+        self.downloads.push(DownloadsChange{
+            identifier: identifier.to_string(),
+            from,
+            to,
+        });
+    }
+
+    pub fn add_merge(&mut self, identifier: &str, field: &str){
+        self.merges.push(FieldChange{
+            identifier: identifier.to_string(),
+            field: field.to_string(),
+        });
+    }
+
+    pub fn add_conflict(&mut self, identifier: &str, field: &str){
+        self.conflicts.push(FieldChange{
+            identifier: identifier.to_string(),
+            field: field.to_string(),
+        });
+    }
+
+    pub fn add_deletion(&mut self, path: &str){
+        self.deletions.push(path.to_string());
+    }
+
+    pub fn add_announcement(&mut self, publisher: &str, identifier: &str, content: &str){
+        self.announcements.push(Announcement{
+            publisher: publisher.to_string(),
+            identifier: identifier.to_string(),
+            content: content.to_string(),
+        });
+    }
+
+    pub fn add_skipped(&mut self, publisher: &str, identifier: &str){
+        self.skipped.push(SkippedAnnouncement{
+            publisher: publisher.to_string(),
+            identifier: identifier.to_string(),
+        });
+    }
+
+    pub fn add_push(&mut self, identifier: &str, field: &str, remote: Option<Value>,
+            local: Value){
+        self.pushes.push(MetadataPush{
+            identifier: identifier.to_string(),
+            field: field.to_string(),
+            remote,
+            local,
+        });
+    }
+
+    pub fn to_json(&self) -> String{
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl Display for Plan{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "New episodes ({}):", self.new_episodes.len())?;
+        for episode in &self.new_episodes{
+            writeln!(f, "  - {}: {}", episode.identifier, episode.title)?;
+        }
+        writeln!(f, "Downloads ({}):", self.downloads.len())?;
+        for change in &self.downloads{
+            writeln!(f, "  - {}: {} -> {}", change.identifier, change.from, change.to)?;
+        }
+        writeln!(f, "Fields taken from Internet Archive ({}):", self.merges.len())?;
+        for change in &self.merges{
+            writeln!(f, "  - {}: {}", change.identifier, change.field)?;
+        }
+        writeln!(f, "Conflicts ({}):", self.conflicts.len())?;
+        for change in &self.conflicts{
+            writeln!(f, "  - {}: {}", change.identifier, change.field)?;
+        }
+        writeln!(f, "Files to delete ({}):", self.deletions.len())?;
+        for path in &self.deletions{
+            writeln!(f, "  - {}", path)?;
+        }
+        writeln!(f, "Announcements ({}):", self.announcements.len())?;
+        for announcement in &self.announcements{
+            writeln!(f, "  - [{}] {}", announcement.publisher, announcement.identifier)?;
+            for line in announcement.content.lines(){
+                writeln!(f, "      {}", line)?;
+            }
+        }
+        writeln!(f, "Announcements left pending, without credentials ({}):",
+            self.skipped.len())?;
+        for skipped in &self.skipped{
+            writeln!(f, "  - [{}] {}", skipped.publisher, skipped.identifier)?;
+        }
+        writeln!(f, "Metadata to push ({}):", self.pushes.len())?;
+        for push in &self.pushes{
+            writeln!(f, "  - {}: {}", push.identifier, push.field)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Plan;
+
+    #[test]
+    fn test_plan_report(){
+        let mut plan = Plan::default();
+        plan.add_new_episode("ep2", "Segundo");
+        plan.add_downloads("ep1", 10, 12);
+        plan.add_deletion("public/index.html");
+        plan.add_announcement("mastodon", "ep2", "Nuevo episodio");
+        plan.add_skipped("bluesky", "ep2");
+        plan.add_push("ep1", "title", None, serde_json::json!("Uno"));
+        let text = plan.to_string();
+        assert!(text.contains("  - ep2: Segundo"));
+        assert!(text.contains("  - ep1: 10 -> 12"));
+        assert!(text.contains("  - public/index.html"));
+        assert!(text.contains("      Nuevo episodio"));
+        assert!(text.contains("  - [bluesky] ep2"));
+        assert!(text.contains("  - ep1: title"));
+        let json: serde_json::Value = serde_json::from_str(&plan.to_json()).unwrap();
+        assert_eq!(json["downloads"][0]["to"], 12);
+        assert_eq!(json["announcements"][0]["publisher"], "mastodon");
+        assert_eq!(json["pushes"][0]["local"], "Uno");
+    }
+}
@@ -0,0 +1,269 @@
+use chrono::{SecondsFormat, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+use tracing::{info, debug, warn};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Publisher, Receipt, super::{
+    config::{Podcast, Post},
+    error::Error,
+    striptags,
+}};
+
+const DEFAULT_SERVICE: &str = "https://bsky.social";
+/// Maximum length of a post, in graphemes
+const MAX_GRAPHEMES: usize = 300;
+/// Maximum size of the image of the card
+const MAX_THUMB_SIZE: u64 = 1_000_000;
+
+static URL: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://[^\s]+").unwrap());
+
+pub fn get_bluesky_client(podcast: &Podcast) -> Option<Bluesky>{
+    let identifier = std::env::var("BLUESKY_IDENTIFIER").ok()?;
+    let password = std::env::var("BLUESKY_APP_PASSWORD").ok()?;
+    let service = std::env::var("BLUESKY_SERVICE")
+        .unwrap_or_else(|_| DEFAULT_SERVICE.to_string());
+    Some(Bluesky::new(&service, &identifier, &password, podcast))
+}
+
+/// Publishes in Bluesky through the AT Protocol, logging in with an app
+/// password.
+pub struct Bluesky{
+    service: String,
+    identifier: String,
+    password: String,
+    podcast: Podcast,
+}
+
+struct Session{
+    access_jwt: String,
+    did: String,
+}
+
+/// Link in the text of a post, in bytes.
+#[derive(Debug, PartialEq)]
+struct Link{
+    start: usize,
+    end: usize,
+    uri: String,
+}
+
+impl Bluesky{
+    pub fn new(service: &str, identifier: &str, password: &str, podcast: &Podcast) -> Self{
+        Self{
+            service: service.trim_end_matches('/').to_string(),
+            identifier: identifier.to_string(),
+            password: password.to_string(),
+            podcast: podcast.clone(),
+        }
+    }
+
+    fn create_session(&self) -> Result<Session, Error>{
+        let url = format!("{}/xrpc/com.atproto.server.createSession", self.service);
+        info!("{}", &url);
+        let session: Value = ureq::post(&url)
+            .send_json(ureq::json!({
+                "identifier": self.identifier,
+                "password": self.password,
+            }))?
+            .into_json()?;
+        match (session["accessJwt"].as_str(), session["did"].as_str()){
+            (Some(access_jwt), Some(did)) => Ok(Session{
+                access_jwt: access_jwt.to_string(),
+                did: did.to_string(),
+            }),
+            _ => Err(Error::new("Bluesky did not return a session")),
+        }
+    }
+
+    /// Uploads the image of the podcast to use it in the card of the post.
+    fn upload_thumb(&self, session: &Session) -> Result<Value, Error>{
+        let response = ureq::get(&self.podcast.image_url).call()?;
+        let mime_type = response.content_type().to_string();
+        let mut image = Vec::new();
+        std::io::Read::read_to_end(
+            &mut std::io::Read::take(response.into_reader(), MAX_THUMB_SIZE + 1),
+            &mut image)?;
+        if image.len() as u64 > MAX_THUMB_SIZE{
+            return Err(Error::new("The image of the podcast is too big for Bluesky"));
+        }
+        let url = format!("{}/xrpc/com.atproto.repo.uploadBlob", self.service);
+        let blob: Value = ureq::post(&url)
+            .set("Authorization", &format!("Bearer {}", session.access_jwt))
+            .set("Content-Type", &mime_type)
+            .send_bytes(&image)?
+            .into_json()?;
+        Ok(blob["blob"].clone())
+    }
+
+    /// Publishes `text` with a card linking to `episode` and returns the uri
+    /// of the post and its url in bsky.app.
+    pub fn post(&self, episode: &Post, text: &str) -> Result<Receipt, Error>{
+        let session = self.create_session()?;
+        let (text, links) = prepare(text, MAX_GRAPHEMES);
+        let facets: Vec<Value> = links.iter()
+            .map(|link| ureq::json!({
+                "index": {"byteStart": link.start, "byteEnd": link.end},
+                "features": [{"$type": "app.bsky.richtext.facet#link", "uri": link.uri}],
+            }))
+            .collect();
+        let mut external = ureq::json!({
+            "uri": self.podcast.get_url(&format!("{}/", episode.slug)),
+            "title": episode.title,
+            "description": striptags(episode.excerpt.clone()).trim(),
+        });
+        match self.upload_thumb(&session){
+            Ok(thumb) => external["thumb"] = thumb,
+            Err(err) => warn!("Can not upload the image of the podcast. {:#}", err),
+        }
+        let url = format!("{}/xrpc/com.atproto.repo.createRecord", self.service);
+        let record: Value = ureq::post(&url)
+            .set("Authorization", &format!("Bearer {}", session.access_jwt))
+            .send_json(ureq::json!({
+                "repo": session.did,
+                "collection": "app.bsky.feed.post",
+                "record": {
+                    "$type": "app.bsky.feed.post",
+                    "text": text,
+                    "facets": facets,
+                    "embed": {
+                        "$type": "app.bsky.embed.external",
+                        "external": external,
+                    },
+                    "langs": [self.podcast.language],
+                    "createdAt": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                },
+            }))?
+            .into_json()?;
+        info!("Send post");
+        debug!("Post: {text}");
+        let uri = record["uri"].as_str().map(str::to_string);
+        let url = uri.as_deref()
+            .and_then(|uri| uri.rsplit_once('/'))
+            .map(|(_, rkey)| format!("https://bsky.app/profile/{}/post/{}", session.did, rkey));
+        Ok(Receipt{ id: uri, url })
+    }
+}
+
+impl Publisher for Bluesky{
+    fn announce(&self, episode: &Post, rendered: &str) -> Result<Receipt, Error>{
+        self.post(episode, rendered)
+    }
+}
+
+/// Cuts `text` to `limit` graphemes, ending it with an ellipsis, and
+/// returns it with its links. A link that does not fit is left out whole.
+fn prepare(text: &str, limit: usize) -> (String, Vec<Link>){
+    let text = text.trim();
+    let links: Vec<Link> = URL.find_iter(text)
+        .map(|found| {
+            let uri = found.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"', '\'']);
+            Link{
+                start: found.start(),
+                end: found.start() + uri.len(),
+                uri: uri.to_string(),
+            }
+        })
+        .collect();
+    if text.graphemes(true).count() <= limit{
+        return (text.to_string(), links);
+    }
+    // Room for the ellipsis
+    let (cut, _) = text.grapheme_indices(true).nth(limit - 1).unwrap();
+    let cut = links.iter()
+        .find(|link| link.start < cut && cut < link.end)
+        .map_or(cut, |link| link.start);
+    let text = text[..cut].trim_end();
+    let links = links.into_iter()
+        .filter(|link| link.end <= text.len())
+        .collect();
+    (format!("{}…", text), links)
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{Server, Matcher};
+    use unicode_segmentation::UnicodeSegmentation;
+    use super::{prepare, Bluesky, Link};
+    use super::super::super::feed::tests::{get_podcast, get_post};
+
+    #[test]
+    fn test_prepare(){
+        let text = "Nuevo episodio 🦀 https://example.com/uno/. ¡Escúchalo!";
+        let (prepared, links) = prepare(text, 300);
+        assert_eq!(prepared, text);
+        let start = text.find("https").unwrap();
+        assert_eq!(links, vec![Link{
+            start,
+            end: start + "https://example.com/uno/".len(),
+            uri: "https://example.com/uno/".to_string(),
+        }]);
+        let text = format!("{} https://example.com/uno/", "ñ".repeat(290));
+        let (prepared, links) = prepare(&text, 300);
+        assert_eq!(prepared, format!("{}…", "ñ".repeat(290)));
+        assert!(links.is_empty());
+        let text = "🦀".repeat(400);
+        let (prepared, _) = prepare(&text, 300);
+        assert_eq!(prepared.graphemes(true).count(), 300);
+    }
+
+    #[test]
+    fn test_post(){
+        let mut server = Server::new();
+        let session = server.mock("POST", "/xrpc/com.atproto.server.createSession")
+            .match_body(Matcher::Json(serde_json::json!({
+                "identifier": "atareao.bsky.social",
+                "password": "app-password",
+            })))
+            .with_body(r#"{"accessJwt": "jwt", "did": "did:plc:abc"}"#)
+            .create();
+        let image = server.mock("GET", "/cover.png")
+            .with_header("content-type", "image/png")
+            .with_body("png")
+            .create();
+        let blob = server.mock("POST", "/xrpc/com.atproto.repo.uploadBlob")
+            .match_header("authorization", "Bearer jwt")
+            .match_header("content-type", "image/png")
+            .match_body("png")
+            .with_body(r#"{"blob": {"$type": "blob", "ref": {"$link": "cid"}, "mimeType": "image/png", "size": 3}}"#)
+            .create();
+        let record = server.mock("POST", "/xrpc/com.atproto.repo.createRecord")
+            .match_header("authorization", "Bearer jwt")
+            .match_body(Matcher::PartialJson(serde_json::json!({
+                "repo": "did:plc:abc",
+                "collection": "app.bsky.feed.post",
+                "record": {
+                    "text": "Uno https://example.com/uno/",
+                    "facets": [{
+                        "index": {"byteStart": 4, "byteEnd": 28},
+                        "features": [{"$type": "app.bsky.richtext.facet#link",
+                            "uri": "https://example.com/uno/"}],
+                    }],
+                    "embed": {
+                        "$type": "app.bsky.embed.external",
+                        "external": {
+                            "uri": "https://example.com/uno/",
+                            "title": "Uno & <dos>",
+                            "description": "Primero",
+                            "thumb": {"ref": {"$link": "cid"}},
+                        },
+                    },
+                },
+            })))
+            .with_body(r#"{"uri": "at://did:plc:abc/app.bsky.feed.post/3k2a", "cid": "cid"}"#)
+            .create();
+        let mut podcast = get_podcast();
+        podcast.image_url = format!("{}/cover.png", server.url());
+        let bluesky = Bluesky::new(&server.url(), "atareao.bsky.social", "app-password",
+            &podcast);
+        let receipt = bluesky.post(&get_post(), "Uno https://example.com/uno/").unwrap();
+        session.assert();
+        image.assert();
+        blob.assert();
+        record.assert();
+        assert_eq!(receipt.id.as_deref(), Some("at://did:plc:abc/app.bsky.feed.post/3k2a"));
+        assert_eq!(receipt.url.as_deref(),
+            Some("https://bsky.app/profile/did:plc:abc/post/3k2a"));
+    }
+}
@@ -0,0 +1,118 @@
+use serde_json::Value;
+use tracing::{info, debug};
+
+use super::{Publisher, Receipt, super::{
+    config::{Podcast, Post},
+    error::Error,
+    striptags,
+}};
+
+/// Maximum length of the content of a message, in characters
+const MAX_CONTENT: usize = 2000;
+/// Maximum length of the title of an embed, in characters
+const MAX_TITLE: usize = 256;
+/// Maximum length of the description of an embed, in characters
+const MAX_DESCRIPTION: usize = 4096;
+
+pub fn get_discord_client(podcast: &Podcast) -> Option<Discord>{
+    let url = std::env::var("DISCORD_WEBHOOK_URL").ok()?;
+    Some(Discord::new(&url, podcast))
+}
+
+/// Sends the announcements to a Discord channel through a webhook, with an
+/// embed that links to the episode.
+pub struct Discord{
+    url: String,
+    podcast: Podcast,
+}
+
+impl Discord{
+    pub fn new(url: &str, podcast: &Podcast) -> Self{
+        Self{
+            url: url.to_string(),
+            podcast: podcast.clone(),
+        }
+    }
+
+    /// Sends `content` with an embed of `episode` and returns the id of the
+    /// message.
+    pub fn execute(&self, episode: &Post, content: &str) -> Result<Receipt, Error>{
+        // Without wait Discord does not return the message
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}wait=true", self.url, separator);
+        let content = cut(content.trim(), MAX_CONTENT);
+        let response: Value = ureq::post(&url)
+            .send_json(ureq::json!({
+                "content": content,
+                "embeds": [{
+                    "title": cut(&episode.title, MAX_TITLE),
+                    "description": cut(striptags(episode.excerpt.clone()).trim(), MAX_DESCRIPTION),
+                    "url": self.podcast.get_url(&format!("{}/", episode.slug)),
+                    "timestamp": episode.date.to_rfc3339(),
+                    "author": {"name": cut(&self.podcast.title, MAX_TITLE)},
+                    "thumbnail": {"url": self.podcast.image_url},
+                }],
+            }))?
+            .into_json()?;
+        info!("Send message");
+        debug!("Message: {content}");
+        Ok(Receipt{
+            id: response["id"].as_str().map(str::to_string),
+            url: None,
+        })
+    }
+}
+
+impl Publisher for Discord{
+    fn announce(&self, episode: &Post, rendered: &str) -> Result<Receipt, Error>{
+        self.execute(episode, rendered)
+    }
+}
+
+/// The first `limit` characters of `text`, ending with an ellipsis if it is
+/// longer.
+fn cut(text: &str, limit: usize) -> String{
+    match text.char_indices().nth(limit - 1){
+        Some((index, _)) if text.chars().count() > limit => format!("{}…", &text[..index]),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{Server, Matcher};
+    use super::{cut, Discord};
+    use super::super::super::feed::tests::{get_podcast, get_post};
+
+    #[test]
+    fn test_cut(){
+        assert_eq!(cut("corto", 10), "corto");
+        assert_eq!(cut("ñañaña", 4), "ñañ…");
+        assert_eq!(cut("ñañaña", 4).chars().count(), 4);
+    }
+
+    #[test]
+    fn test_execute(){
+        let mut server = Server::new();
+        let mock = server.mock("POST", "/api/webhooks/1/token")
+            .match_query(Matcher::UrlEncoded("wait".to_string(), "true".to_string()))
+            .match_body(Matcher::Json(serde_json::json!({
+                "content": "Nuevo episodio: Uno",
+                "embeds": [{
+                    "title": "Uno & <dos>",
+                    "description": "Primero",
+                    "url": "https://example.com/uno/",
+                    "timestamp": "2024-01-01T10:00:00+00:00",
+                    "author": {"name": "Rust & <Linux>"},
+                    "thumbnail": {"url": "https://example.com/cover.png"},
+                }],
+            })))
+            .with_body(r#"{"id": "1234", "channel_id": "5678"}"#)
+            .create();
+        let discord = Discord::new(&format!("{}/api/webhooks/1/token", server.url()),
+            &get_podcast());
+        let receipt = discord.execute(&get_post(), "Nuevo episodio: Uno\n").unwrap();
+        mock.assert();
+        assert_eq!(receipt.id.as_deref(), Some("1234"));
+    }
+}
@@ -1,4 +1,6 @@
-use tracing::{info, error, debug};
+use tracing::{info, debug};
+
+use super::{Publisher, Receipt, super::{config::Post, error::Error}};
 
 pub fn get_mastodon_client() -> Option<Mastodon>{
     match std::env::var("MASTODON_TOKEN"){
@@ -25,30 +27,36 @@ impl Mastodon{
         }
     }
 
-    pub fn post(&self, message: &str){
+    /// Publishes `message` and returns the id and url of the status.
+    pub fn post(&self, message: &str) -> Result<Receipt, Error>{
         let url = format!("https://{}/api/v1/statuses", self.instance);
         info!("{}", &url);
-        match ureq::post(&url)
+        let response = ureq::post(&url)
             .set("Authorization", &format!("Bearer {}", self.access_token))
             .set("Content-Type", "application/json")
             .set("Accept", "application/json")
             .send_json(ureq::json!({
                 "status": message,
-            })){
-                Ok(response) => {
-                    if response.status() == 200{
-                        info!("Send status");
-                        debug!("Status: {message}");
-                    }else{
-                        let status_code = response.status();
-                        let error = response.into_string().unwrap();
-                        error!("Error sending status. HTTP Error: {status_code}. {error}")
-                    }
-                },
-                Err(error) => {
-                    error!("Error sending status: {error}")
-                },
-            }
+            }))?;
+        if response.status() == 200{
+            info!("Send status");
+            debug!("Status: {message}");
+            let status: serde_json::Value = response.into_json()?;
+            Ok(Receipt{
+                id: status["id"].as_str().map(str::to_string),
+                url: status["url"].as_str().map(str::to_string),
+            })
+        }else{
+            let status_code = response.status();
+            let error = response.into_string()?;
+            Err(Error::new(&format!("Error sending status. HTTP Error: {status_code}. {error}")))
+        }
+    }
+}
+
+impl Publisher for Mastodon{
+    fn announce(&self, _episode: &Post, rendered: &str) -> Result<Receipt, Error>{
+        self.post(rendered)
     }
 }
 
@@ -61,7 +69,7 @@ mod tests {
     fn send_status_test(){
         dotenv().ok();
         if let Some(mastodon) = get_mastodon_client(){
-            mastodon.post("Esto es una prueba")
+            mastodon.post("Esto es una prueba").unwrap();
         }
     }
 }
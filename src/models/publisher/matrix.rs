@@ -0,0 +1,126 @@
+use serde_json::Value;
+use tracing::{info, debug};
+
+use super::{Publisher, Receipt, super::{
+    config::Post,
+    error::Error,
+    striptags,
+    utils::encode,
+}};
+
+pub fn get_matrix_client(room: &str) -> Option<Matrix>{
+    match std::env::var("MATRIX_ACCESS_TOKEN"){
+        Ok(token) => {
+            match std::env::var("MATRIX_HOMESERVER"){
+                Ok(homeserver) => Some(Matrix::new(&homeserver, &token, room)),
+                Err(_) => None,
+            }
+        },
+        Err(_) => None,
+    }
+}
+
+/// Sends the announcements to a Matrix room.
+pub struct Matrix{
+    homeserver: String,
+    access_token: String,
+    room: String,
+}
+
+impl Matrix{
+    pub fn new(homeserver: &str, access_token: &str, room: &str) -> Self{
+        Self{
+            homeserver: homeserver.trim_end_matches('/').to_string(),
+            access_token: access_token.to_string(),
+            room: room.to_string(),
+        }
+    }
+
+    /// Sends `html` to the room, with its plain text as fallback, and
+    /// returns the id of the event. The transaction is named after
+    /// `identifier`, so that the homeserver ignores a retry of a message it
+    /// already got.
+    pub fn send(&self, identifier: &str, html: &str) -> Result<Receipt, Error>{
+        let url = format!("{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+            self.homeserver, encode(&self.room), encode(&format!("iapodcast-{}", identifier)));
+        info!("{}", &url);
+        let html = html.trim();
+        let response: Value = ureq::put(&url)
+            .set("Authorization", &format!("Bearer {}", self.access_token))
+            .send_json(ureq::json!({
+                "msgtype": "m.text",
+                "body": to_plain(html),
+                "format": "org.matrix.custom.html",
+                "formatted_body": html,
+            }))?
+            .into_json()?;
+        info!("Send message");
+        debug!("Message: {html}");
+        let id = response["event_id"].as_str().map(str::to_string);
+        let url = id.as_deref()
+            .map(|id| format!("https://matrix.to/#/{}/{}", self.room, id));
+        Ok(Receipt{ id, url })
+    }
+}
+
+impl Publisher for Matrix{
+    fn announce(&self, episode: &Post, rendered: &str) -> Result<Receipt, Error>{
+        self.send(&episode.identifier, rendered)
+    }
+}
+
+/// Plain text of `html`, with a line break for each paragraph and `<br>`.
+fn to_plain(html: &str) -> String{
+    let html = html.replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("</p>", "</p>\n");
+    let text = striptags(html)
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&#x2f;", "/")
+        .replace("&amp;", "&");
+    text.lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::{Server, Matcher};
+    use super::{to_plain, Matrix};
+
+    #[test]
+    fn test_to_plain(){
+        assert_eq!(to_plain("<p>Nuevo: <strong>Uno &amp; dos</strong></p>\n<p>https:&#x2f;&#x2f;example.com</p>"),
+            "Nuevo: Uno & dos\n\nhttps://example.com");
+    }
+
+    #[test]
+    fn test_send(){
+        let mut server = Server::new();
+        let mock = server.mock("PUT",
+                "/_matrix/client/v3/rooms/%21sala%3Aexample.com/send/m.room.message/iapodcast-uno")
+            .match_header("authorization", "Bearer token")
+            .match_body(Matcher::Json(serde_json::json!({
+                "msgtype": "m.text",
+                "body": "Nuevo episodio: Uno",
+                "format": "org.matrix.custom.html",
+                "formatted_body": "<p>Nuevo episodio: <a href=\"https://example.com/uno/\">Uno</a></p>",
+            })))
+            .with_body(r#"{"event_id": "$evento"}"#)
+            .create();
+        let matrix = Matrix::new(&server.url(), "token", "!sala:example.com");
+        let receipt = matrix.send("uno",
+            "<p>Nuevo episodio: <a href=\"https://example.com/uno/\">Uno</a></p>\n").unwrap();
+        mock.assert();
+        assert_eq!(receipt.id.as_deref(), Some("$evento"));
+        assert_eq!(receipt.url.as_deref(), Some("https://matrix.to/#/!sala:example.com/$evento"));
+    }
+}
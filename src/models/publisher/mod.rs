@@ -1,11 +1,70 @@
+mod bluesky;
+mod discord;
 mod mastodon;
+mod matrix;
 mod telegram;
+mod webhook;
 
-pub use mastodon::{
-    Mastodon,
-    get_mastodon_client
-};
-pub use telegram::{
-    Telegram,
-    get_telegram_client
+use tracing::{debug, error};
+
+use bluesky::get_bluesky_client;
+use discord::get_discord_client;
+use mastodon::get_mastodon_client;
+use matrix::get_matrix_client;
+use telegram::get_telegram_client;
+use webhook::get_webhook_client;
+
+use super::{
+    config::{Configuration, Post, PublisherConfig, PublisherKind},
+    error::Error,
 };
+
+/// Post created by an announcement, as far as the publisher tells.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Receipt{
+    pub id: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Network where the new episodes are announced.
+pub trait Publisher{
+    /// Sends `rendered`, the announcement of `episode` rendered with the
+    /// template of the publisher.
+    fn announce(&self, episode: &Post, rendered: &str) -> Result<Receipt, Error>;
+}
+
+/// Returns the publisher of `config`, or None if its credentials are not in
+/// the environment.
+pub fn get_publisher(config: &PublisherConfig, configuration: &Configuration)
+        -> Option<Box<dyn Publisher>>{
+    let publisher: Option<Box<dyn Publisher>> = match config.kind{
+        PublisherKind::Telegram => get_telegram_client(configuration.get_iaclient())
+            .map(|client| Box::new(client) as Box<dyn Publisher>),
+        PublisherKind::Mastodon => get_mastodon_client()
+            .map(|client| Box::new(client) as Box<dyn Publisher>),
+        PublisherKind::Bluesky => get_bluesky_client(configuration.get_podcast())
+            .map(|client| Box::new(client) as Box<dyn Publisher>),
+        PublisherKind::Matrix => match &config.room{
+            Some(room) => get_matrix_client(room)
+                .map(|client| Box::new(client) as Box<dyn Publisher>),
+            None => {
+                error!("Set the room of the matrix publisher");
+                None
+            }
+        },
+        PublisherKind::Discord => get_discord_client(configuration.get_podcast())
+            .map(|client| Box::new(client) as Box<dyn Publisher>),
+        PublisherKind::Webhook => match &config.url{
+            Some(url) => get_webhook_client(url, config.secret.as_deref())
+                .map(|client| Box::new(client) as Box<dyn Publisher>),
+            None => {
+                error!("Set the url of the webhook publisher {}", config.get_name());
+                None
+            }
+        },
+    };
+    if publisher.is_none(){
+        debug!("Publisher {} is not configured", config.get_name());
+    }
+    publisher
+}
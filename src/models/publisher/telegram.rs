@@ -1,15 +1,19 @@
-use tracing::{info, debug, error};
+use tracing::{info, debug};
+
+use super::{Publisher, Receipt, super::{archive::IAClient, config::Post, error::Error}};
 
 pub struct Telegram{
     access_token: String,
     chat_id: String,
+    iaclient: IAClient,
 }
 
-pub fn get_telegram_client() -> Option<Telegram>{
+/// The audios of the episodes are sent from `iaclient`.
+pub fn get_telegram_client(iaclient: &IAClient) -> Option<Telegram>{
     match std::env::var("TELEGRAM_TOKEN"){
         Ok(token) => {
             match std::env::var("TELEGRAM_CHAT_ID"){
-                Ok(chat_id) => Some(Telegram::new(&token, &chat_id)),
+                Ok(chat_id) => Some(Telegram::new(&token, &chat_id, iaclient)),
                 Err(_) => None,
             }
         },
@@ -18,20 +22,23 @@ pub fn get_telegram_client() -> Option<Telegram>{
 }
 
 impl Telegram{
-    pub fn new(access_token: &str, chat_id: &str) -> Self{
+    pub fn new(access_token: &str, chat_id: &str, iaclient: &IAClient) -> Self{
         Self{
             access_token: access_token.to_string(),
             chat_id: chat_id.to_string(),
+            iaclient: iaclient.clone(),
         }
     }
 
-    pub fn send_audio(&self, audio: &str, caption: &str){
+    /// Sends `audio` with `caption` and returns the id of the message, and
+    /// its url if the chat is public.
+    pub fn send_audio(&self, audio: &str, caption: &str) -> Result<Receipt, Error>{
         let url = format!("https://api.telegram.org/bot{}/sendAudio",
             self.access_token);
         info!("url  {}", url);
         let content = Self::prepare(caption);
 
-        match ureq::post(&url)
+        let response = ureq::post(&url)
             .set("Content-Type", "application/json")
             .set("Accept", "application/json")
             .send_json(ureq::json!({
@@ -39,23 +46,28 @@ impl Telegram{
                 "audio": audio,
                 "caption": content,
                 "parse_mode": "HTML",
-            })){
-            Ok(response) => {
-                if response.status() == 200 {
-                    info!("Send audio");
-                    debug!("Audio: {audio}");
-                }else{
-                    let status_code = response.status();
-                    let error = response.into_string().unwrap();
-                    error!("Error sending audio. HTTP Error: {status_code}. {error}")
-                }
-            },
-            Err(e) => {
-                error!("Error sending audio: {e}")
-            }
+            }))?;
+        if response.status() == 200 {
+            info!("Send audio");
+            debug!("Audio: {audio}");
+            let message: serde_json::Value = response.into_json()?;
+            Ok(Self::get_receipt(&message["result"]))
+        }else{
+            let status_code = response.status();
+            let error = response.into_string()?;
+            Err(Error::new(&format!("Error sending audio. HTTP Error: {status_code}. {error}")))
         }
     }
 
+    fn get_receipt(message: &serde_json::Value) -> Receipt{
+        let id = message["message_id"].as_i64().map(|id| id.to_string());
+        let url = match (message["chat"]["username"].as_str(), &id){
+            (Some(username), Some(id)) => Some(format!("https://t.me/{}/{}", username, id)),
+            _ => None,
+        };
+        Receipt{ id, url }
+    }
+
     fn prepare(text: &str) -> String{
         text.chars()
             .map(|c| match c {
@@ -66,11 +78,33 @@ impl Telegram{
     }
 }
 
+impl Publisher for Telegram{
+    /// Sends the audio of `episode` with `rendered` as caption.
+    fn announce(&self, episode: &Post, rendered: &str) -> Result<Receipt, Error>{
+        let audio = self.iaclient.get_download_url(&episode.identifier, &episode.filename);
+        self.send_audio(&audio, rendered)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use dotenv::dotenv;
     use std::env;
     use super::Telegram;
+    use super::super::super::archive::IAClient;
+
+    #[test]
+    fn test_get_receipt(){
+        let message = serde_json::json!({
+            "message_id": 42,
+            "chat": {"id": -100, "username": "papafriki"},
+        });
+        let receipt = Telegram::get_receipt(&message);
+        assert_eq!(receipt.id.as_deref(), Some("42"));
+        assert_eq!(receipt.url.as_deref(), Some("https://t.me/papafriki/42"));
+        let receipt = Telegram::get_receipt(&serde_json::json!({"message_id": 7, "chat": {}}));
+        assert_eq!(receipt.url, None);
+    }
 
     #[test]
     fn send_audio_test(){
@@ -86,8 +120,9 @@ Ya sabéis, poco a poco irá llegando a vuestro programa de podcast favorito, a
         println!("{}, {}, {}, {}", token, chat_id, audio, caption);
         println!("==============================================");
         
-        let telegram = Telegram::new(&token, &chat_id);
-        telegram.send_audio(&audio, caption);
+        let iaclient = IAClient::new("atareao", "papafriki");
+        let telegram = Telegram::new(&token, &chat_id, &iaclient);
+        telegram.send_audio(&audio, caption).unwrap();
     }
 }
 
@@ -0,0 +1,113 @@
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use tracing::{info, debug};
+
+use super::{Publisher, Receipt, super::{
+    config::Post,
+    error::Error,
+}};
+
+/// Header with the signature of the payload, as GitHub does
+const SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+
+/// Returns the webhook of `url`, signed with the key in the environment
+/// variable `secret` if there is one. None if that variable is not set, so
+/// that nothing is sent without signature.
+pub fn get_webhook_client(url: &str, secret: Option<&str>) -> Option<Webhook>{
+    match secret{
+        Some(variable) => std::env::var(variable).ok()
+            .map(|key| Webhook::new(url, Some(&key))),
+        None => Some(Webhook::new(url, None)),
+    }
+}
+
+/// Sends the rendered template, that must be a JSON, to any url.
+pub struct Webhook{
+    url: String,
+    key: Option<String>,
+}
+
+impl Webhook{
+    pub fn new(url: &str, key: Option<&str>) -> Self{
+        Self{
+            url: url.to_string(),
+            key: key.map(str::to_string),
+        }
+    }
+
+    /// Posts `body` and returns the id and url of the response, if it is a
+    /// JSON that has them.
+    pub fn send(&self, body: &str) -> Result<Receipt, Error>{
+        if let Err(e) = serde_json::from_str::<Value>(body){
+            return Err(Error::new(&format!("The payload of the webhook is not a JSON. {e}")));
+        }
+        info!("{}", &self.url);
+        let mut request = ureq::post(&self.url)
+            .set("Content-Type", "application/json");
+        if let Some(key) = &self.key{
+            request = request.set(SIGNATURE_HEADER, &format!("sha256={}", sign(key, body)));
+        }
+        let response = request.send_string(body)?.into_string()?;
+        info!("Send payload");
+        debug!("Payload: {body}");
+        let response: Value = serde_json::from_str(&response).unwrap_or_default();
+        Ok(Receipt{
+            id: get_string(&response["id"]),
+            url: get_string(&response["url"]),
+        })
+    }
+}
+
+impl Publisher for Webhook{
+    fn announce(&self, _episode: &Post, rendered: &str) -> Result<Receipt, Error>{
+        self.send(rendered)
+    }
+}
+
+/// HMAC-SHA256 of `body` with `key`, in hexadecimal.
+fn sign(key: &str, body: &str) -> String{
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+        .expect("HMAC takes keys of any size");
+    mac.update(body.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+fn get_string(value: &Value) -> Option<String>{
+    match value{
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockito::Server;
+    use super::{sign, Webhook};
+
+    #[test]
+    fn test_sign(){
+        // RFC 4231, test case 2
+        assert_eq!(sign("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+
+    #[test]
+    fn test_send(){
+        let body = r#"{"title": "Uno"}"#;
+        let mut server = Server::new();
+        let mock = server.mock("POST", "/hook")
+            .match_header("content-type", "application/json")
+            .match_header("x-hub-signature-256", format!("sha256={}", sign("secreto", body)).as_str())
+            .match_body(body)
+            .with_body(r#"{"id": 42}"#)
+            .create();
+        let webhook = Webhook::new(&format!("{}/hook", server.url()), Some("secreto"));
+        let receipt = webhook.send(body).unwrap();
+        mock.assert();
+        assert_eq!(receipt.id.as_deref(), Some("42"));
+        assert!(receipt.url.is_none());
+        assert!(webhook.send("{\"title\": ").is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+use super::{config::Post, striptags};
+
+/// Entry of the search index. Every text is plain, without HTML.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SearchEntry{
+    pub title: String,
+    pub url: String,
+    pub date: String,
+    pub subjects: Vec<String>,
+    pub excerpt: String,
+    pub content: String,
+}
+
+impl SearchEntry{
+    pub fn new(post: &Post, base_url: &str) -> Self{
+        Self{
+            title: post.title.clone(),
+            url: format!("{}/{}/", base_url.trim_end_matches('/'), post.slug),
+            date: post.date.format("%Y-%m-%d").to_string(),
+            subjects: post.subject.clone(),
+            excerpt: to_text(&post.excerpt),
+            content: to_text(&post.content),
+        }
+    }
+}
+
+/// Search index of the episodes and pages, as compact JSON.
+pub fn get_search_index(posts: &[Post], pages: &[Post], base_url: &str) -> String{
+    let entries: Vec<SearchEntry> = posts.iter()
+        .chain(pages.iter())
+        .map(|post| SearchEntry::new(post, base_url))
+        .collect();
+    serde_json::to_string(&entries).unwrap()
+}
+
+/// Strips the tags, unescapes the usual entities and collapses the
+/// whitespace.
+fn to_text(html: &str) -> String{
+    striptags(html.to_string())
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_text;
+
+    #[test]
+    fn test_to_text(){
+        assert_eq!(to_text("<p>Uso de <code>serde</code> &amp;\n   <em>tokio</em></p>\n"),
+            "Uso de serde & tokio");
+    }
+}
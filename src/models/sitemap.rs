@@ -0,0 +1,97 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use quick_xml::{
+    events::{BytesDecl, BytesText, Event},
+    Writer,
+};
+use std::io::Cursor;
+
+use super::{config::RobotsConfig, error::Error};
+
+const NAMESPACE: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapUrl{
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct Sitemap{
+    pub urls: Vec<SitemapUrl>,
+}
+
+impl Sitemap{
+    pub fn add(&mut self, loc: &str, lastmod: Option<DateTime<Utc>>){
+        self.urls.push(SitemapUrl{
+            loc: loc.to_string(),
+            lastmod,
+        });
+    }
+
+    pub fn to_xml(&self) -> Result<String, Error>{
+        let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+        writer.create_element("urlset")
+            .with_attribute(("xmlns", NAMESPACE))
+            .write_inner_content(|writer| {
+                for url in &self.urls{
+                    writer.create_element("url")
+                        .write_inner_content(|writer| {
+                            writer.create_element("loc")
+                                .write_text_content(BytesText::new(&url.loc))?;
+                            if let Some(lastmod) = &url.lastmod{
+                                let lastmod = lastmod.to_rfc3339_opts(SecondsFormat::Secs, true);
+                                writer.create_element("lastmod")
+                                    .write_text_content(BytesText::new(&lastmod))?;
+                            }
+                            Ok(())
+                        })?;
+                }
+                Ok(())
+            })?;
+        let content = writer.into_inner().into_inner();
+        String::from_utf8(content).map_err(|e| Error::new(&e.to_string()))
+    }
+}
+
+/// Content of `robots.txt` with the rules of `config` and the url of the
+/// sitemap.
+pub fn get_robots(config: &RobotsConfig, sitemap_url: &str) -> String{
+    let mut robots = format!("User-agent: {}\n", config.user_agent);
+    for path in &config.allow{
+        robots.push_str(&format!("Allow: {}\n", path));
+    }
+    for path in &config.disallow{
+        robots.push_str(&format!("Disallow: {}\n", path));
+    }
+    if config.allow.is_empty() && config.disallow.is_empty(){
+        robots.push_str("Disallow:\n");
+    }
+    robots.push_str(&format!("\nSitemap: {}\n", sitemap_url));
+    robots
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use super::{Sitemap, get_robots, RobotsConfig};
+
+    #[test]
+    fn test_sitemap(){
+        let mut sitemap = Sitemap::default();
+        sitemap.add("https://example.com/", None);
+        sitemap.add("https://example.com/uno/?a=1&b=2",
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap()));
+        let xml = sitemap.to_xml().unwrap();
+        assert!(xml.contains("<loc>https://example.com/uno/?a=1&amp;b=2</loc>"));
+        assert!(xml.contains("<lastmod>2024-01-01T10:00:00Z</lastmod>"));
+        assert_eq!(xml.matches("<lastmod>").count(), 1);
+
+        let config = RobotsConfig{
+            disallow: vec!["/statistics/".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(get_robots(&config, "https://example.com/sitemap.xml"),
+            "User-agent: *\nDisallow: /statistics/\n\nSitemap: https://example.com/sitemap.xml\n");
+    }
+}
@@ -0,0 +1,75 @@
+use serde::Serialize;
+
+use super::{config::Post, utils::get_slug};
+
+/// Subject of the episodes, with the episodes that have it.
+#[derive(Debug, Serialize, Clone)]
+pub struct Tag{
+    pub name: String,
+    pub slug: String,
+    pub count: usize,
+    pub posts: Vec<Post>,
+}
+
+impl Tag{
+    /// Groups `posts` by the slug of their subjects, keeping the order of
+    /// the posts. The name of each tag is the first spelling found. The tags
+    /// are sorted by slug.
+    pub fn from_posts(posts: &[Post]) -> Vec<Tag>{
+        let mut tags: Vec<Tag> = Vec::new();
+        for post in posts{
+            for subject in &post.subject{
+                let slug = get_slug(subject);
+                if slug.is_empty(){
+                    continue;
+                }
+                match tags.iter_mut().find(|tag| tag.slug == slug){
+                    Some(tag) => {
+                        if !tag.posts.iter().any(|other| other.identifier == post.identifier){
+                            tag.posts.push(post.clone());
+                            tag.count += 1;
+                        }
+                    },
+                    None => tags.push(Tag{
+                        name: subject.trim().to_string(),
+                        slug,
+                        count: 1,
+                        posts: vec![post.clone()],
+                    }),
+                }
+            }
+        }
+        tags.sort_by(|a, b| a.slug.cmp(&b.slug));
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tag;
+    use super::super::config::Post;
+
+    #[test]
+    fn test_from_posts(){
+        let posts = vec![
+            Post{
+                identifier: "dos".to_string(),
+                subject: vec!["Neovim".to_string(), "rust".to_string()],
+                ..Default::default()
+            },
+            Post{
+                identifier: "uno".to_string(),
+                subject: vec!["neovim".to_string(), "Neovim ".to_string()],
+                ..Default::default()
+            },
+        ];
+        let tags = Tag::from_posts(&posts);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].name, "Neovim");
+        assert_eq!(tags[0].slug, "neovim");
+        assert_eq!(tags[0].count, 2);
+        assert_eq!(tags[0].posts[1].identifier, "uno");
+        assert_eq!(tags[1].slug, "rust");
+        assert_eq!(tags[1].count, 1);
+    }
+}
@@ -67,7 +67,30 @@ pub fn get_first_words(content: &str, number: usize) -> &str{
     }
 }
 
-#[allow(dead_code)]
+/// MIME type of an audio file from its extension.
+pub fn get_mime_type(filename: &str) -> &'static str{
+    let extension = filename.rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str(){
+        "m4a" => "audio/x-m4a",
+        "ogg" => "audio/ogg",
+        "opus" => "audio/opus",
+        "flac" => "audio/flac",
+        _ => "audio/mpeg",
+    }
+}
+
+/// Percent-encodes every byte of `value` but the unreserved characters.
+pub fn encode(value: &str) -> String{
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
 pub fn get_unix_time(ymd: &str) -> DateTime<Utc>{
     let nd = NaiveDate::parse_from_str(ymd, "%Y-%m-%d").unwrap();
     let nt = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
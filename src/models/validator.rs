@@ -0,0 +1,373 @@
+use quick_xml::{events::Event, Reader, XmlVersion};
+use chrono::DateTime;
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+};
+
+use super::{config::Post, error::Error};
+
+const EXPLICIT_VALUES: [&str; 5] = ["true", "false", "yes", "no", "clean"];
+const IMAGE_EXTENSIONS: [&str; 3] = [".jpg", ".jpeg", ".png"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity{
+    Error,
+    Warning,
+}
+
+/// Rule of Apple Podcasts or Podcast Index broken by the feed. `episode` is
+/// `None` when it belongs to the channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation{
+    pub severity: Severity,
+    pub episode: Option<String>,
+    pub message: String,
+}
+
+impl Display for Violation{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.episode{
+            Some(episode) => write!(f, "Episode {}: {}", episode, self.message),
+            None => write!(f, "Channel: {}", self.message),
+        }
+    }
+}
+
+/// Minimal tree of the feed, enough to look for tags and attributes.
+#[derive(Debug, Default)]
+struct Element{
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<Element>,
+}
+
+impl Element{
+    fn child(&self, name: &str) -> Option<&Element>{
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element>{
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str>{
+        self.attributes.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child_text(&self, name: &str) -> Option<&str>{
+        self.child(name)
+            .map(|child| child.text.trim())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+struct Checker{
+    violations: Vec<Violation>,
+}
+
+impl Checker{
+    fn error(&mut self, episode: Option<&str>, message: String){
+        self.add(Severity::Error, episode, message);
+    }
+
+    fn warning(&mut self, episode: Option<&str>, message: String){
+        self.add(Severity::Warning, episode, message);
+    }
+
+    fn add(&mut self, severity: Severity, episode: Option<&str>, message: String){
+        self.violations.push(Violation{
+            severity,
+            episode: episode.map(str::to_string),
+            message,
+        });
+    }
+
+    fn required(&mut self, element: &Element, episode: Option<&str>, names: &[&str]){
+        for name in names{
+            if element.child_text(name).is_none(){
+                self.error(episode, format!("<{name}> is missing or empty"));
+            }
+        }
+    }
+
+    fn image(&mut self, episode: Option<&str>, tag: &str, url: Option<&str>){
+        match url.map(str::trim).filter(|url| !url.is_empty()){
+            Some(url) => {
+                if !url.starts_with("http://") && !url.starts_with("https://"){
+                    self.error(episode, format!("{tag} `{url}` is not an http url"));
+                }
+                if url.contains(' '){
+                    self.error(episode, format!("{tag} `{url}` has spaces"));
+                }
+                let lowercase = url.to_lowercase();
+                if !IMAGE_EXTENSIONS.iter().any(|extension| lowercase.ends_with(extension)){
+                    self.error(episode, format!("{tag} `{url}` must be a JPEG or PNG"));
+                }
+            },
+            None => self.error(episode, format!("{tag} is missing")),
+        }
+    }
+
+    fn channel(&mut self, channel: &Element){
+        self.required(channel, None, &["title", "link", "description", "language"]);
+        self.image(None, "<itunes:image>",
+            channel.child("itunes:image").and_then(|image| image.attribute("href")));
+        if let Some(image) = channel.child("image"){
+            self.image(None, "<image><url>", image.child_text("url"));
+        }
+        match channel.child_text("itunes:explicit"){
+            Some(explicit) if EXPLICIT_VALUES.contains(&explicit) => {},
+            Some(explicit) => self.error(None,
+                format!("<itunes:explicit> `{explicit}` is not valid")),
+            None => self.error(None, "<itunes:explicit> is missing".to_string()),
+        }
+        let mut categories = channel.children("itunes:category").peekable();
+        if categories.peek().is_none(){
+            self.error(None, "<itunes:category> is missing".to_string());
+        }
+        for category in categories{
+            self.category(category);
+        }
+        if channel.child_text("podcast:guid").is_none(){
+            self.warning(None, "<podcast:guid> is missing".to_string());
+        }
+        if channel.child("item").is_none(){
+            self.warning(None, "There are no episodes".to_string());
+        }
+    }
+
+    fn category(&mut self, category: &Element){
+        match category.attribute("text").map(str::trim){
+            Some(text) if !text.is_empty() => {},
+            _ => self.error(None, "<itunes:category> has an empty text".to_string()),
+        }
+        for subcategory in category.children("itunes:category"){
+            self.category(subcategory);
+        }
+    }
+
+    fn item(&mut self, item: &Element, episode: &str, guids: &mut HashSet<String>){
+        let episode = Some(episode);
+        self.required(item, episode, &["title"]);
+        match item.child("enclosure"){
+            Some(enclosure) => {
+                match enclosure.attribute("url"){
+                    Some(url) if url.starts_with("http://") || url.starts_with("https://") => {},
+                    Some(url) => self.error(episode,
+                        format!("Enclosure url `{url}` is not an http url")),
+                    None => self.error(episode, "Enclosure has no url".to_string()),
+                }
+                match enclosure.attribute("length").map(|length| length.parse::<u64>()){
+                    Some(Ok(length)) if length > 0 => {},
+                    Some(_) => self.error(episode,
+                        "Enclosure length must be a positive number of bytes".to_string()),
+                    None => self.error(episode, "Enclosure has no length".to_string()),
+                }
+                match enclosure.attribute("type"){
+                    Some(mime_type) if mime_type.starts_with("audio/")
+                        || mime_type.starts_with("video/") => {},
+                    Some(mime_type) => self.error(episode,
+                        format!("Enclosure type `{mime_type}` is not audio or video")),
+                    None => self.error(episode, "Enclosure has no type".to_string()),
+                }
+            },
+            None => self.error(episode, "<enclosure> is missing".to_string()),
+        }
+        match item.child_text("guid"){
+            Some(guid) => {
+                if !guids.insert(guid.to_string()){
+                    self.error(episode, format!("<guid> `{guid}` is repeated"));
+                }
+            },
+            None => self.error(episode, "<guid> is missing or empty".to_string()),
+        }
+        match item.child_text("pubDate"){
+            Some(date) => {
+                if DateTime::parse_from_rfc2822(date).is_err(){
+                    self.error(episode, format!("<pubDate> `{date}` is not an RFC 2822 date"));
+                }
+            },
+            None => self.error(episode, "<pubDate> is missing".to_string()),
+        }
+        match item.child_text("itunes:duration"){
+            Some(duration) => {
+                if !is_duration(duration){
+                    self.error(episode,
+                        format!("<itunes:duration> `{duration}` is not [[hh:]mm:]ss"));
+                }
+            },
+            None => self.warning(episode, "<itunes:duration> is missing".to_string()),
+        }
+        if let Some(image) = item.child("itunes:image"){
+            self.image(episode, "<itunes:image>", image.attribute("href"));
+        }
+    }
+}
+
+/// Parses the rendered feed and checks it against the requirements of
+/// Apple Podcasts and Podcast Index. The items are identified by the
+/// identifier of their post.
+pub fn check_feed(xml: &str, posts: &[Post]) -> Result<Vec<Violation>, Error>{
+    let root = parse(xml)?;
+    let mut checker = Checker{ violations: Vec::new() };
+    let channel = root.child("rss")
+        .and_then(|rss| rss.child("channel"))
+        .ok_or_else(|| Error::new("The feed has no <rss><channel>"))?;
+    checker.channel(channel);
+    let mut guids = HashSet::new();
+    for (i, item) in channel.children("item").enumerate(){
+        let episode = get_episode(item, posts)
+            .unwrap_or_else(|| format!("#{}", i + 1));
+        checker.item(item, &episode, &mut guids);
+    }
+    Ok(checker.violations)
+}
+
+/// Identifier of the post whose slug is in the link or guid of the item.
+fn get_episode(item: &Element, posts: &[Post]) -> Option<String>{
+    let link = item.child_text("link").unwrap_or_default();
+    let guid = item.child_text("guid").unwrap_or_default();
+    posts.iter()
+        .find(|post| {
+            let path = format!("/{}/", post.slug);
+            link.ends_with(&path) || guid.ends_with(&path)
+        })
+        .map(|post| post.identifier.clone())
+        .or_else(|| item.child_text("title").map(str::to_string))
+}
+
+fn is_duration(value: &str) -> bool{
+    let parts: Vec<&str> = value.split(':').collect();
+    parts.len() <= 3
+        && parts.iter().enumerate().all(|(i, part)| {
+            !part.is_empty()
+                && part.chars().all(|c| c.is_ascii_digit())
+                && (i == 0 || (part.len() == 2 && part < &"60"))
+        })
+}
+
+fn parse(xml: &str) -> Result<Element, Error>{
+    let mut reader = Reader::from_str(xml);
+    let mut stack = vec![Element::default()];
+    loop{
+        let event = reader.read_event().map_err(|e| Error::new(&format!(
+            "Invalid XML at position {}. {e}", reader.error_position())))?;
+        let empty = matches!(event, Event::Empty(_));
+        match event{
+            Event::Start(start) | Event::Empty(start) => {
+                let mut element = Element{
+                    name: start.name().as_ref().to_string(),
+                    ..Default::default()
+                };
+                for attribute in start.attributes(){
+                    let attribute = attribute.map_err(|e| Error::new(&e.to_string()))?;
+                    let value = attribute.normalized_value(XmlVersion::Implicit1_0)
+                        .map_err(|e| Error::new(&e.to_string()))?;
+                    element.attributes.push((
+                        attribute.key.as_ref().to_string(),
+                        value.to_string()));
+                }
+                if empty{
+                    stack.last_mut().unwrap().children.push(element);
+                }else{
+                    stack.push(element);
+                }
+            },
+            Event::End(_) => {
+                let element = stack.pop().unwrap();
+                match stack.last_mut(){
+                    Some(parent) => parent.children.push(element),
+                    None => return Err(Error::new("Unbalanced XML")),
+                }
+            },
+            Event::Text(text) => stack.last_mut().unwrap().text.push_str(&text.xml10_content()),
+            Event::CData(data) => stack.last_mut().unwrap().text.push_str(&data.xml10_content()),
+            Event::GeneralRef(reference) => {
+                let text = stack.last_mut().unwrap();
+                match reference.resolve_char_ref(){
+                    Ok(Some(c)) => text.text.push(c),
+                    _ => text.text.push_str(match reference.into_inner().as_ref(){
+                        "amp" => "&",
+                        "lt" => "<",
+                        "gt" => ">",
+                        "quot" => "\"",
+                        "apos" => "'",
+                        _ => "",
+                    }),
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+    match stack.pop(){
+        Some(root) if stack.is_empty() => Ok(root),
+        _ => Err(Error::new("Unbalanced XML")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_feed, is_duration, Severity};
+
+    #[test]
+    fn test_check_feed(){
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+<channel>
+    <title>Prueba</title>
+    <link>https://example.com</link>
+    <description>Prueba &amp; más</description>
+    <language>es-es</language>
+    <itunes:explicit>false</itunes:explicit>
+    <itunes:image href="https://example.com/cover.png" />
+    <itunes:category text="Technology">
+    <itunes:category text="" />
+    </itunes:category>
+    <item>
+        <title>Uno</title>
+        <link>https://example.com/uno/</link>
+        <guid>/uno/</guid>
+        <pubDate>Mon, 01 Jan 2024 11:00:00 +0100</pubDate>
+        <enclosure url="https://example.com/uno.mp3" length="100" type="audio/mpeg" />
+        <itunes:duration>1:02:03</itunes:duration>
+    </item>
+    <item>
+        <title>Dos</title>
+        <guid>/uno/</guid>
+        <pubDate>Mon, 01 Jan 2024 11:00:00</pubDate>
+        <enclosure url="https://example.com/dos.mp3" length="0" type="audio/mpeg" />
+        <itunes:duration>3723</itunes:duration>
+    </item>
+</channel>
+</rss>"#;
+        let violations = check_feed(xml, &[]).unwrap();
+        let errors: Vec<String> = violations.iter()
+            .filter(|violation| violation.severity == Severity::Error)
+            .map(|violation| violation.to_string())
+            .collect();
+        assert_eq!(errors, vec![
+            "Channel: <itunes:category> has an empty text",
+            "Episode Dos: Enclosure length must be a positive number of bytes",
+            "Episode Dos: <guid> `/uno/` is repeated",
+            "Episode Dos: <pubDate> `Mon, 01 Jan 2024 11:00:00` is not an RFC 2822 date",
+        ]);
+        assert!(violations.iter().any(|violation|
+            violation.to_string() == "Channel: <podcast:guid> is missing"));
+        assert!(check_feed("<rss><channel></rss>", &[]).is_err());
+    }
+
+    #[test]
+    fn test_is_duration(){
+        assert!(is_duration("3723"));
+        assert!(is_duration("1:02:03"));
+        assert!(is_duration("62:03"));
+        assert!(!is_duration("1:2:3"));
+        assert!(!is_duration("1:02:63"));
+        assert!(!is_duration(""));
+    }
+}
@@ -0,0 +1,153 @@
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::broadcast::{error::RecvError, Sender},
+};
+use tracing::{debug, error, info};
+use std::path::{Component, Path, PathBuf};
+
+/// Path of the server-sent events stream that asks the pages to reload.
+const LIVERELOAD: &str = "/__livereload";
+const LIVERELOAD_SCRIPT: &str = "<script>new EventSource(\"/__livereload\").onmessage = () => location.reload();</script>";
+
+/// Serves the files in `public` over HTTP until the process is stopped. The
+/// pages are reloaded in the browser every time `reload` is sent.
+pub async fn serve(public: &str, host: &str, port: u16, reload: Sender<()>) {
+    let address = format!("{host}:{port}");
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("Can not listen on {}. {:#}", &address, err);
+            std::process::exit(1);
+        }
+    };
+    info!("Serving {} on http://{}", public, &address);
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let public = public.to_string();
+                let reload = reload.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = handle(stream, &public, &reload).await {
+                        error!("Can not answer request. {:#}", err);
+                    }
+                });
+            }
+            Err(err) => error!("Can not accept connection. {:#}", err),
+        }
+    }
+}
+
+async fn handle(mut stream: TcpStream, public: &str, reload: &Sender<()>)
+        -> tokio::io::Result<()> {
+    let mut buffer = [0; 4096];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+    debug!("Request: {}", path);
+    if path == LIVERELOAD {
+        return livereload(stream, reload).await;
+    }
+    let (status, content_type, mut body) = match resolve(public, path).await {
+        Some(file) => match tokio::fs::read(&file).await {
+            Ok(body) => ("200 OK", get_content_type(&file), body),
+            Err(_) => not_found(public).await,
+        },
+        None => not_found(public).await,
+    };
+    if content_type.starts_with("text/html") {
+        body = inject_livereload(body);
+    }
+    let header = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(header.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.flush().await
+}
+
+/// Keeps the connection open and sends an event every time the site is
+/// rebuilt.
+async fn livereload(mut stream: TcpStream, reload: &Sender<()>) -> tokio::io::Result<()> {
+    let mut receiver = reload.subscribe();
+    stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n").await?;
+    stream.flush().await?;
+    loop {
+        match receiver.recv().await {
+            Ok(()) | Err(RecvError::Lagged(_)) => {
+                stream.write_all(b"data: reload\n\n").await?;
+                stream.flush().await?;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Adds the script that listens for reloads before the end of the body.
+fn inject_livereload(body: Vec<u8>) -> Vec<u8> {
+    let html = String::from_utf8_lossy(&body);
+    match html.rfind("</body>") {
+        Some(position) => format!("{}{}{}", &html[..position], LIVERELOAD_SCRIPT,
+            &html[position..]).into_bytes(),
+        None => format!("{}{}", html, LIVERELOAD_SCRIPT).into_bytes(),
+    }
+}
+
+async fn resolve(public: &str, path: &str) -> Option<PathBuf> {
+    let path = path.split(['?', '#']).next().unwrap_or("/");
+    let relative = Path::new(path.trim_start_matches('/'));
+    if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let mut file = Path::new(public).join(relative);
+    if tokio::fs::metadata(&file).await.ok()?.is_dir() {
+        file.push("index.html");
+    }
+    Some(file)
+}
+
+async fn not_found(public: &str) -> (&'static str, &'static str, Vec<u8>) {
+    let body = tokio::fs::read(format!("{}/404.html", public))
+        .await
+        .unwrap_or_else(|_| b"Not found".to_vec());
+    ("404 Not Found", "text/html; charset=utf-8", body)
+}
+
+fn get_content_type(file: &Path) -> &'static str {
+    match file.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inject_livereload, LIVERELOAD_SCRIPT};
+
+    #[test]
+    fn test_inject_livereload(){
+        let body = inject_livereload(b"<html><body><p>Hola</p></body></html>".to_vec());
+        assert_eq!(String::from_utf8(body).unwrap(),
+            format!("<html><body><p>Hola</p>{}</body></html>", LIVERELOAD_SCRIPT));
+        let body = inject_livereload(b"<p>Hola</p>".to_vec());
+        assert_eq!(String::from_utf8(body).unwrap(),
+            format!("<p>Hola</p>{}", LIVERELOAD_SCRIPT));
+    }
+}
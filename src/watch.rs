@@ -0,0 +1,135 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver},
+    time::{timeout_at, Instant},
+};
+use tracing::{debug, error};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::models::config::Configuration;
+
+/// Time to wait for more changes before rebuilding, as editors usually
+/// write a file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// What has to be done after a change, from the cheapest to the most
+/// expensive. Every kind includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change{
+    /// Generate the site again with the templates already loaded
+    Assets,
+    /// Read the templates again and generate the site
+    Site,
+    /// Read the configuration and generate the site again
+    Configuration,
+}
+
+/// Watches the templates, episodes, pages and assets directories and the
+/// configuration file.
+pub struct SiteWatcher{
+    // Dropping the watcher stops it
+    _watcher: RecommendedWatcher,
+    receiver: UnboundedReceiver<notify::Result<Event>>,
+    roots: Vec<(PathBuf, Change)>,
+}
+
+impl SiteWatcher{
+    pub fn new(configuration: &Configuration, config: &str) -> notify::Result<Self>{
+        let (sender, receiver) = unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            sender.send(event).ok();
+        })?;
+        let mut roots = Vec::new();
+        for (directory, change) in [
+            (configuration.get_templates(), Change::Site),
+            (configuration.get_episodes(), Change::Site),
+            (configuration.get_pages(), Change::Site),
+            (configuration.get_assets(), Change::Assets),
+        ]{
+            let Ok(directory) = Path::new(directory).canonicalize() else {
+                continue;
+            };
+            watcher.watch(&directory, RecursiveMode::Recursive)?;
+            roots.push((directory, change));
+        }
+        // Editors usually replace the file instead of writing it, so the
+        // directory is watched instead of the file
+        let config = Path::new(config).canonicalize()?;
+        if let Some(parent) = config.parent(){
+            watcher.watch(parent, RecursiveMode::NonRecursive)?;
+        }
+        roots.push((config, Change::Configuration));
+        Ok(Self{ _watcher: watcher, receiver, roots })
+    }
+
+    /// Waits until something changes and returns what has to be done. The
+    /// changes made in a short span are merged in one.
+    pub async fn next(&mut self) -> Option<Change>{
+        let mut change = None;
+        let mut deadline = None;
+        loop {
+            let event = match deadline{
+                None => self.receiver.recv().await,
+                Some(deadline) => match timeout_at(deadline, self.receiver.recv()).await{
+                    Ok(event) => event,
+                    Err(_) => return change,
+                },
+            };
+            match event{
+                Some(Ok(event)) => {
+                    if matches!(event.kind, EventKind::Access(_)){
+                        continue;
+                    }
+                    for path in &event.paths{
+                        if let Some(found) = classify(&self.roots, path){
+                            debug!("Changed {}", path.display());
+                            change = change.max(Some(found));
+                            deadline.get_or_insert_with(|| Instant::now() + DEBOUNCE);
+                        }
+                    }
+                }
+                Some(Err(err)) => error!("Can not watch changes. {:#}", err),
+                None => return change,
+            }
+        }
+    }
+}
+
+/// Returns what has to be done when `path` changes, if anything. Hidden and
+/// backup files of the editors are ignored.
+fn classify(roots: &[(PathBuf, Change)], path: &Path) -> Option<Change>{
+    let filename = path.file_name()?.to_string_lossy();
+    if filename.starts_with('.') || filename.ends_with('~'){
+        return None;
+    }
+    roots.iter()
+        .filter(|(root, _)| path.starts_with(root))
+        .map(|(_, change)| *change)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::{classify, Change};
+
+    #[test]
+    fn test_classify(){
+        let roots = vec![
+            (PathBuf::from("/site/templates"), Change::Site),
+            (PathBuf::from("/site/assets"), Change::Assets),
+            (PathBuf::from("/site/config.yml"), Change::Configuration),
+        ];
+        let check = |path: &str| classify(&roots, &PathBuf::from(path));
+        assert_eq!(check("/site/templates/post.html"), Some(Change::Site));
+        assert_eq!(check("/site/assets/css/style.css"), Some(Change::Assets));
+        assert_eq!(check("/site/config.yml"), Some(Change::Configuration));
+        assert_eq!(check("/site/templates/.post.html.swp"), None);
+        assert_eq!(check("/site/templates/post.html~"), None);
+        assert_eq!(check("/site/other.yml"), None);
+        assert_eq!(check("/site/assets-old/style.css"), None);
+    }
+}